@@ -0,0 +1,9 @@
+fn main() {
+    // Same UI and resource embedding as the firmware, see ../build.rs
+    slint_build::compile_with_config(
+        "../ui/printerdemo.slint",
+        slint_build::CompilerConfiguration::new()
+            .embed_resources(slint_build::EmbedResourcesKind::EmbedForSoftwareRenderer),
+    )
+    .unwrap();
+}
@@ -0,0 +1,123 @@
+//! Submits and manages the print jobs of the device over its job serial port
+//!
+//! Usage:
+//!
+//! ```text
+//! jobs <serial port> submit <title> [pages] [size] [owner]
+//! jobs <serial port> list
+//! jobs <serial port> cancel <id>
+//! jobs <serial port> watch
+//! ```
+//!
+//! `watch` prints the progress notifications until interrupted. Set the port up first, e.g.
+//! `stty -F /dev/ttyUSB2 115200 raw -echo`.
+
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hpm_slint_host::job_protocol::Message;
+use hpm_slint_host::jobs::JobClient;
+use hpm_slint_host::printer_queue::JobInfo;
+
+const USAGE: &str = "usage: jobs <serial port> submit <title> [pages] [size] [owner]
+       jobs <serial port> list
+       jobs <serial port> cancel <id>
+       jobs <serial port> watch";
+
+/// Current time as shown in the queue, e.g. `11:41 25/01/21`, in UTC
+fn submission_date() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // Civil date from the days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:02}:{:02} {day:02}/{month:02}/{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        year % 100
+    )
+}
+
+fn run(port: &str, command: &[String]) -> Result<(), String> {
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(port)
+        .map_err(|e| format!("{port}: {e}"))?;
+    let mut client = JobClient::new(file);
+    let io_error = |e: std::io::Error| format!("{port}: {e}");
+
+    match command {
+        [command, title, rest @ ..] if command == "submit" && rest.len() <= 3 => {
+            let pages = match rest.first() {
+                Some(pages) => pages.parse().map_err(|_| format!("invalid page count {pages:?}"))?,
+                None => 1,
+            };
+            let info = JobInfo {
+                title: title.clone(),
+                owner: rest
+                    .get(2)
+                    .cloned()
+                    .unwrap_or_else(|| std::env::var("USER").unwrap_or_default()),
+                pages,
+                size: rest.get(1).cloned().unwrap_or_default(),
+                submission_date: submission_date(),
+            };
+            let id = client.submit(info).map_err(io_error)?;
+            println!("{id}");
+        }
+        [command] if command == "list" => {
+            for job in client.list().map_err(io_error)? {
+                println!(
+                    "{:>4}  {:<9} {:>3}%  {:>3}p  {:<8} {:<16} {}",
+                    job.id,
+                    format!("{:?}", job.status),
+                    job.progress,
+                    job.info.pages,
+                    job.info.size,
+                    job.info.submission_date,
+                    job.info.title
+                );
+            }
+        }
+        [command, id] if command == "cancel" => {
+            let id = id.parse().map_err(|_| format!("invalid job id {id:?}"))?;
+            if !client.cancel(id).map_err(io_error)? {
+                return Err(format!("no job {id}"));
+            }
+        }
+        [command] if command == "watch" => loop {
+            match client.next_notification().map_err(io_error)? {
+                Message::Progress { id, progress } => println!("{id}: {progress}%"),
+                Message::Completed { id } => println!("{id}: completed"),
+                Message::Failed { id } => println!("{id}: failed"),
+                _ => {}
+            }
+        },
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((port, command)) = args.split_first() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    match run(port, command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
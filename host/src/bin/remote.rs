@@ -0,0 +1,51 @@
+//! Runs a touch script on the device over its serial port
+//!
+//! Usage: `remote <serial port> <script> [output directory]`, see [`hpm_slint_host::script`] for the script format.
+//!
+//! Set the port up first, e.g. `stty -F /dev/ttyUSB0 2000000 raw -echo`. The same scripts run in the simulator.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use hpm_slint_host::remote::RemoteClient;
+use hpm_slint_host::script;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (port, script_path, out_dir) = match &args[..] {
+        [port, script_path] => (port, script_path, PathBuf::from(".")),
+        [port, script_path, out_dir] => (port, script_path, PathBuf::from(out_dir)),
+        _ => {
+            eprintln!("usage: remote <serial port> <script> [output directory]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let commands = match std::fs::read_to_string(script_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| script::parse(&text).map_err(|e| e.to_string()))
+    {
+        Ok(commands) => commands,
+        Err(e) => {
+            eprintln!("{script_path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let port = match std::fs::OpenOptions::new().read(true).write(true).open(port) {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("{port}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(e) = std::fs::create_dir_all(&out_dir) {
+        eprintln!("{}: {e}", out_dir.display());
+        return ExitCode::FAILURE;
+    }
+    if let Err(e) = script::run(&mut RemoteClient::new(port), &commands, &out_dir) {
+        eprintln!("{e}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
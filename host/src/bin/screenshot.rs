@@ -0,0 +1,64 @@
+//! Receives a screenshot from the device and writes it as PNG
+//!
+//! Usage: `screenshot <serial port or capture file> <output.png>`
+//!
+//! The serial port is read as a plain file, set it up first, e.g. `stty -F /dev/ttyUSB0 2000000 raw`. Then request a
+//! capture on the device, for instance with `set var SCREENSHOT_REQUEST = 1` from gdb.
+
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+
+use hpm_slint_host::capture::FrameDecoder;
+use hpm_slint_host::write_png_sized;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [input, output] = &args[..] else {
+        eprintln!("usage: screenshot <serial port or capture file> <output.png>");
+        return ExitCode::FAILURE;
+    };
+
+    let mut input = match std::fs::File::open(input) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("{input}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut decoder = FrameDecoder::new();
+    let mut buf = [0; 4096];
+    let frame = loop {
+        match input.read(&mut buf) {
+            Ok(0) => {
+                eprintln!("end of input before a complete frame");
+                return ExitCode::FAILURE;
+            }
+            Ok(n) => {
+                if let Some(frame) = decoder.push(&buf[..n]) {
+                    break frame;
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+    if decoder.errors() > 0 {
+        eprintln!("warning: {} corrupted packets dropped", decoder.errors());
+    }
+
+    if let Err(e) = write_png_sized(
+        Path::new(output),
+        frame.width as u32,
+        frame.height as u32,
+        &frame.to_rgb8(),
+    ) {
+        eprintln!("{output}: {e}");
+        return ExitCode::FAILURE;
+    }
+    println!("{output}: {}x{}", frame.width, frame.height);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,109 @@
+//! Decoder of the screenshot packets sent by the firmware, the format is described in `src/screenshot/mod.rs`
+
+use crate::link::{PacketReader, MAX_DEVICE_PAYLOAD};
+
+#[path = "../../src/screenshot/protocol.rs"]
+mod protocol;
+
+pub use protocol::{BEGIN, END, FORMAT_RGB565, LINE};
+
+/// A captured frame, pixels are RGB565 in row-major order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub width: u16,
+    pub height: u16,
+    pub pixels: Vec<u16>,
+}
+
+impl Frame {
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|p| crate::rgb565_to_rgb8(*p)).collect()
+    }
+}
+
+/// Reassembles frames from the byte stream, garbage and corrupted packets are skipped
+pub struct FrameDecoder {
+    reader: PacketReader,
+    frame: Option<Frame>,
+    lines: u16,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        FrameDecoder {
+            reader: PacketReader::with_max_payload(MAX_DEVICE_PAYLOAD),
+            frame: None,
+            lines: 0,
+        }
+    }
+
+    /// Packets dropped because of a bad checksum
+    pub fn errors(&self) -> usize {
+        self.reader.errors
+    }
+
+    /// Feed received bytes, returns the frame once its end packet is received
+    pub fn push(&mut self, bytes: &[u8]) -> Option<Frame> {
+        self.reader.push(bytes);
+        let mut done = None;
+        while let Some((kind, payload)) = self.reader.next_packet() {
+            if let Some(frame) = self.handle(kind, &payload) {
+                done = Some(frame);
+            }
+        }
+        done
+    }
+
+    /// Handle a packet, other kinds than the screenshot ones are ignored
+    ///
+    /// Returns the frame once its end packet is received.
+    pub fn handle(&mut self, kind: u8, payload: &[u8]) -> Option<Frame> {
+        let u16_at = |i: usize| payload.get(i..i + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        match kind {
+            BEGIN => {
+                let (Some(width), Some(height)) = (u16_at(0), u16_at(2)) else {
+                    return None;
+                };
+                if payload.get(4) != Some(&FORMAT_RGB565) {
+                    eprintln!("warning: unknown pixel format {:?}", payload.get(4));
+                    return None;
+                }
+                self.frame = Some(Frame {
+                    width,
+                    height,
+                    pixels: vec![0; width as usize * height as usize],
+                });
+                self.lines = 0;
+            }
+            LINE => {
+                let frame = self.frame.as_mut()?;
+                let (Some(y), Some(x)) = (u16_at(0), u16_at(2)) else {
+                    return None;
+                };
+                self.lines += 1;
+                if y >= frame.height {
+                    return None;
+                }
+                let row = &mut frame.pixels[y as usize * frame.width as usize..][..frame.width as usize];
+                for (dst, src) in row.iter_mut().skip(x as usize).zip(payload[4..].chunks_exact(2)) {
+                    *dst = u16::from_le_bytes([src[0], src[1]]);
+                }
+            }
+            END => {
+                let frame = self.frame.take()?;
+                if let Some(sent) = u16_at(0).filter(|sent| *sent != self.lines) {
+                    eprintln!("warning: {} of {sent} lines received", self.lines);
+                }
+                return Some(frame);
+            }
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,99 @@
+//! Client of the job submission protocol of the firmware, described in `src/job_protocol.rs`
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+
+use crate::job_protocol::{self, FrameReader, JobEntry, Message, Request};
+use crate::printer_queue::JobInfo;
+
+/// Whether the message is a notification rather than the reply to a request
+fn is_notification(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::Progress { .. } | Message::Completed { .. } | Message::Failed { .. }
+    )
+}
+
+fn protocol_error(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Submits and manages the print jobs of a device connected over `port`, usually its serial port
+pub struct JobClient<P> {
+    port: P,
+    reader: FrameReader,
+    /// Notifications received while waiting for a reply
+    notifications: VecDeque<Message>,
+}
+
+impl<P: Read + Write> JobClient<P> {
+    pub fn new(port: P) -> Self {
+        JobClient {
+            port,
+            reader: FrameReader::new(),
+            notifications: VecDeque::new(),
+        }
+    }
+
+    fn read_message(&mut self) -> std::io::Result<Message> {
+        let mut buf = [0; 256];
+        loop {
+            if let Some(message) = self.reader.next_frame::<Message>() {
+                return message.map_err(|e| protocol_error(format!("invalid message: {e}")));
+            }
+            match self.port.read(&mut buf)? {
+                0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                n => self.reader.push(&buf[..n]),
+            }
+        }
+    }
+
+    fn request(&mut self, request: &Request) -> std::io::Result<Message> {
+        self.port.write_all(&job_protocol::encode(request))?;
+        self.port.flush()?;
+        loop {
+            match self.read_message()? {
+                Message::Invalid => return Err(protocol_error("the device rejected the request".into())),
+                message if is_notification(&message) => self.notifications.push_back(message),
+                message => return Ok(message),
+            }
+        }
+    }
+
+    /// Queue a job, returns its id
+    pub fn submit(&mut self, info: JobInfo) -> std::io::Result<u32> {
+        match self.request(&Request::Submit(info))? {
+            Message::Submitted { id } => Ok(id),
+            reply => Err(protocol_error(format!("unexpected reply {reply:?}"))),
+        }
+    }
+
+    pub fn list(&mut self) -> std::io::Result<Vec<JobEntry>> {
+        match self.request(&Request::List)? {
+            Message::Jobs(jobs) => Ok(jobs),
+            reply => Err(protocol_error(format!("unexpected reply {reply:?}"))),
+        }
+    }
+
+    /// Remove a job from the queue, returns false if there was no such job
+    pub fn cancel(&mut self, id: u32) -> std::io::Result<bool> {
+        match self.request(&Request::Cancel { id })? {
+            Message::Cancelled { .. } => Ok(true),
+            Message::NotFound { .. } => Ok(false),
+            reply => Err(protocol_error(format!("unexpected reply {reply:?}"))),
+        }
+    }
+
+    /// Wait for the next progress, completion or failure notification
+    pub fn next_notification(&mut self) -> std::io::Result<Message> {
+        if let Some(message) = self.notifications.pop_front() {
+            return Ok(message);
+        }
+        loop {
+            let message = self.read_message()?;
+            if is_notification(&message) {
+                return Ok(message);
+            }
+        }
+    }
+}
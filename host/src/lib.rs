@@ -0,0 +1,302 @@
+//! Host build of the printer demo UI
+//!
+//! Renders `ui/printerdemo.slint` with the software renderer into an in-memory framebuffer the size of the RM67162
+//! panel, the same way the firmware does in `src/slint_ui.rs`. Time only moves forward when told to, so the rendered
+//! frames are reproducible.
+
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+use slint::platform::software_renderer::{LineBufferProvider, MinimalSoftwareWindow, RepaintBufferType, Rgb565Pixel};
+use slint::platform::{Platform, PointerEventButton, WindowAdapter, WindowEvent};
+use slint::{ComponentHandle, LogicalPosition, Model as _, PhysicalSize, PlatformError};
+
+use crate::console::ConsoleDecoder;
+use crate::remote::Property;
+use crate::script::ScriptTarget;
+
+pub mod capture;
+#[path = "../../src/console/decoder.rs"]
+pub mod console;
+#[path = "../../src/job_protocol.rs"]
+pub mod job_protocol;
+pub mod jobs;
+pub mod link;
+#[path = "../../src/print_engine.rs"]
+pub mod print_engine;
+#[path = "../../src/printer_queue.rs"]
+pub mod printer_queue;
+#[path = "../../src/queue_model.rs"]
+pub mod queue_model;
+pub mod remote;
+pub mod script;
+#[path = "../../src/settings.rs"]
+pub mod settings;
+pub mod touch;
+
+slint::include_modules!();
+
+pub const WIDTH: usize = 536;
+pub const HEIGHT: usize = 240;
+
+struct HostPlatform {
+    window: Rc<MinimalSoftwareWindow>,
+    clock: Rc<Cell<Duration>>,
+}
+
+impl Platform for HostPlatform {
+    fn create_window_adapter(&self) -> Result<Rc<dyn WindowAdapter>, PlatformError> {
+        Ok(self.window.clone())
+    }
+
+    fn duration_since_start(&self) -> Duration {
+        self.clock.get()
+    }
+}
+
+thread_local! {
+    /// Slint keeps one platform per thread, every simulator of a thread shares it
+    static PLATFORM: (Rc<MinimalSoftwareWindow>, Rc<Cell<Duration>>) = {
+        let window = MinimalSoftwareWindow::new(RepaintBufferType::ReusedBuffer);
+        let clock = Rc::new(Cell::new(Duration::ZERO));
+        slint::platform::set_platform(Box::new(HostPlatform {
+            window: window.clone(),
+            clock: clock.clone(),
+        }))
+        .unwrap();
+        window.set_size(PhysicalSize::new(WIDTH as u32, HEIGHT as u32));
+        (window, clock)
+    };
+}
+
+/// Framebuffer filled line by line, like `DisplayWrapper` pushes lines to the panel
+struct FramebufferWrapper<'a> {
+    framebuffer: &'a mut [Rgb565Pixel],
+}
+
+impl LineBufferProvider for FramebufferWrapper<'_> {
+    type TargetPixel = Rgb565Pixel;
+    fn process_line(
+        &mut self,
+        line: usize,
+        range: core::ops::Range<usize>,
+        render_fn: impl FnOnce(&mut [Self::TargetPixel]),
+    ) {
+        render_fn(&mut self.framebuffer[line * WIDTH..][range]);
+    }
+}
+
+pub struct Simulator {
+    window: Rc<MinimalSoftwareWindow>,
+    clock: Rc<Cell<Duration>>,
+    framebuffer: Vec<Rgb565Pixel>,
+    main_window: MainWindow,
+}
+
+impl Simulator {
+    pub fn new() -> Self {
+        let (window, clock) = PLATFORM.with(|(window, clock)| (window.clone(), clock.clone()));
+        let main_window = MainWindow::new().unwrap();
+        main_window.show().unwrap();
+        // Like the firmware, type the keys of the on-screen keyboard into the focused text input
+        let main_window_weak = main_window.as_weak();
+        main_window
+            .global::<VirtualKeyboardHandler>()
+            .on_key_pressed(move |text| {
+                let main_window = main_window_weak.unwrap();
+                main_window
+                    .window()
+                    .dispatch_event(WindowEvent::KeyPressed { text: text.clone() });
+                main_window.window().dispatch_event(WindowEvent::KeyReleased { text });
+            });
+        // The previous simulator of this thread may have left a partial frame
+        window.request_redraw();
+
+        Simulator {
+            window,
+            clock,
+            framebuffer: vec![Rgb565Pixel::default(); WIDTH * HEIGHT],
+            main_window,
+        }
+    }
+
+    pub fn main_window(&self) -> &MainWindow {
+        &self.main_window
+    }
+
+    pub fn press(&mut self, x: f32, y: f32) {
+        let position = LogicalPosition::new(x, y);
+        self.dispatch(WindowEvent::PointerMoved { position });
+        self.dispatch(WindowEvent::PointerPressed {
+            position,
+            button: PointerEventButton::Left,
+        });
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.dispatch(WindowEvent::PointerMoved {
+            position: LogicalPosition::new(x, y),
+        });
+    }
+
+    pub fn release(&mut self, x: f32, y: f32) {
+        self.dispatch(WindowEvent::PointerReleased {
+            position: LogicalPosition::new(x, y),
+            button: PointerEventButton::Left,
+        });
+        self.dispatch(WindowEvent::PointerExited);
+    }
+
+    /// Press and release at the same position
+    pub fn tap(&mut self, x: f32, y: f32) {
+        self.press(x, y);
+        self.advance(Duration::from_millis(50));
+        self.release(x, y);
+    }
+
+    /// Press and release a key, `text` is the text of the key or a `slint::platform::Key`
+    pub fn key(&mut self, text: &str) {
+        let text = slint::SharedString::from(text);
+        self.dispatch(WindowEvent::KeyPressed { text: text.clone() });
+        self.dispatch(WindowEvent::KeyReleased { text });
+    }
+
+    /// Type bytes as received by the console UART of the firmware, e.g. from a terminal
+    pub fn type_console(&mut self, bytes: &[u8]) {
+        let mut decoder = ConsoleDecoder::new();
+        let mut keys = Vec::new();
+        for byte in bytes {
+            decoder.push(*byte, |text| keys.push(text));
+        }
+        keys.extend(decoder.timeout());
+        for text in keys {
+            self.key(&text);
+        }
+    }
+
+    pub fn dispatch(&mut self, event: WindowEvent) {
+        slint::platform::update_timers_and_animations();
+        self.window.dispatch_event(event);
+    }
+
+    /// Move the clock forward, running the timers and animations that are due
+    pub fn advance(&mut self, duration: Duration) {
+        // Step through the animations frame by frame, like the firmware loop would
+        const FRAME: Duration = Duration::from_millis(16);
+        let end = self.clock.get() + duration;
+        while self.clock.get() < end {
+            self.clock.set((self.clock.get() + FRAME).min(end));
+            slint::platform::update_timers_and_animations();
+        }
+    }
+
+    /// Advance the clock until the animations are done, up to ten seconds
+    pub fn wait_idle(&mut self) {
+        for _ in 0..10 * 60 {
+            // Animations only count as active while a frame evaluates them, render every frame like the firmware loop
+            self.render();
+            if !self.window.has_active_animations() {
+                return;
+            }
+            self.advance(Duration::from_millis(16));
+        }
+    }
+
+    pub fn query(&self, property: Property) -> i32 {
+        let main_window = &self.main_window;
+        match property {
+            Property::ActivePage => main_window.get_active_page(),
+            Property::QueueLength => main_window.global::<PrinterQueue>().get_printer_queue().row_count() as i32,
+            Property::NightMode => main_window.global::<DemoPalette>().get_night_mode() as i32,
+            Property::CalibrationActive => main_window.global::<Calibration>().get_active() as i32,
+        }
+    }
+
+    /// Render the pending changes and return the whole frame
+    pub fn render(&mut self) -> &[Rgb565Pixel] {
+        slint::platform::update_timers_and_animations();
+        let framebuffer = &mut self.framebuffer;
+        self.window.draw_if_needed(|renderer| {
+            renderer.render_by_line(FramebufferWrapper { framebuffer });
+        });
+        &self.framebuffer
+    }
+
+    /// Render and convert the frame to 8-bit RGB
+    pub fn render_rgb8(&mut self) -> Vec<u8> {
+        self.render().iter().flat_map(|p| rgb565_to_rgb8(p.0)).collect()
+    }
+
+    pub fn save_png(&mut self, path: &Path) -> std::io::Result<()> {
+        write_png(path, &self.render_rgb8())
+    }
+}
+
+impl ScriptTarget for Simulator {
+    fn press(&mut self, x: f32, y: f32) -> std::io::Result<()> {
+        Simulator::press(self, x, y);
+        Ok(())
+    }
+
+    fn move_to(&mut self, x: f32, y: f32) -> std::io::Result<()> {
+        Simulator::move_to(self, x, y);
+        Ok(())
+    }
+
+    fn release(&mut self, x: f32, y: f32) -> std::io::Result<()> {
+        Simulator::release(self, x, y);
+        Ok(())
+    }
+
+    fn key(&mut self, text: &str) -> std::io::Result<()> {
+        Simulator::key(self, text);
+        Ok(())
+    }
+
+    fn wait(&mut self, duration: Duration) -> std::io::Result<()> {
+        self.advance(duration);
+        Ok(())
+    }
+
+    fn wait_idle(&mut self) -> std::io::Result<()> {
+        Simulator::wait_idle(self);
+        Ok(())
+    }
+
+    fn query(&mut self, property: Property) -> std::io::Result<i32> {
+        Ok(Simulator::query(self, property))
+    }
+
+    fn screenshot(&mut self, path: &Path) -> std::io::Result<()> {
+        self.save_png(path)
+    }
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn rgb565_to_rgb8(pixel: u16) -> [u8; 3] {
+    let r = ((pixel >> 11) & 0x1F) as u8;
+    let g = ((pixel >> 5) & 0x3F) as u8;
+    let b = (pixel & 0x1F) as u8;
+    [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2)]
+}
+
+/// Write a `WIDTH` x `HEIGHT` 8-bit RGB frame
+pub fn write_png(path: &Path, rgb: &[u8]) -> std::io::Result<()> {
+    write_png_sized(path, WIDTH as u32, HEIGHT as u32, rgb)
+}
+
+pub fn write_png_sized(path: &Path, width: u32, height: u32, rgb: &[u8]) -> std::io::Result<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgb)?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Packet framing of the serial link to the device, shared with the firmware in `src/link.rs`
+
+use std::io::{Read, Write};
+
+#[path = "../../src/link.rs"]
+mod shared;
+
+pub use shared::{PacketReader, SharedWriter, SYNC};
+
+/// Packets of the device are only bounded by their length field, the screenshot lines are long
+pub const MAX_DEVICE_PAYLOAD: usize = u16::MAX as usize;
+
+/// Encode one packet
+pub fn packet(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    shared::write_packet(&mut packet, kind, &[payload]).unwrap();
+    packet
+}
+
+pub fn write_packet(output: &mut impl Write, kind: u8, payload: &[u8]) -> std::io::Result<()> {
+    output.write_all(&packet(kind, payload))?;
+    output.flush()
+}
+
+impl PacketReader {
+    /// Block on `input` until the next packet
+    pub fn read_packet(&mut self, input: &mut impl Read) -> std::io::Result<(u8, Vec<u8>)> {
+        let mut buf = [0; 4096];
+        loop {
+            if let Some(packet) = self.next_packet() {
+                return Ok(packet);
+            }
+            match input.read(&mut buf) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.push(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Renders the printer demo on the host and writes screenshots
+//!
+//! Usage: `simulator <script> [output directory]`, see [`hpm_slint_host::script`] for the script format.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use hpm_slint_host::{script, Simulator};
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(script_path) = args.next() else {
+        eprintln!("usage: simulator <script> [output directory]");
+        return ExitCode::FAILURE;
+    };
+    let out_dir = args.next().map_or_else(|| PathBuf::from("."), PathBuf::from);
+
+    let commands = match std::fs::read_to_string(&script_path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| script::parse(&text).map_err(|e| e.to_string()))
+    {
+        Ok(commands) => commands,
+        Err(e) => {
+            eprintln!("{script_path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Err(e) = std::fs::create_dir_all(&out_dir) {
+        eprintln!("{}: {e}", out_dir.display());
+        return ExitCode::FAILURE;
+    }
+    let mut simulator = Simulator::new();
+    if let Err(e) = script::run(&mut simulator, &commands, &out_dir) {
+        eprintln!("{e}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
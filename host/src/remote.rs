@@ -0,0 +1,129 @@
+//! Client of the remote control protocol of the firmware, described in `src/remote/mod.rs`
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::capture::{Frame, FrameDecoder};
+use crate::link::{self, PacketReader, MAX_DEVICE_PAYLOAD};
+use crate::script::ScriptTarget;
+
+#[path = "../../src/remote/protocol.rs"]
+mod protocol;
+
+pub use protocol::*;
+
+impl Property {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "active-page" => Some(Property::ActivePage),
+            "queue-length" => Some(Property::QueueLength),
+            "night-mode" => Some(Property::NightMode),
+            "calibration-active" => Some(Property::CalibrationActive),
+            _ => None,
+        }
+    }
+}
+
+fn protocol_error(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Drives the UI of a device connected over `port`, usually its serial port
+pub struct RemoteClient<P> {
+    port: P,
+    reader: PacketReader,
+    frames: FrameDecoder,
+    /// Frame received while waiting for a reply
+    frame: Option<Frame>,
+}
+
+impl<P: Read + Write> RemoteClient<P> {
+    pub fn new(port: P) -> Self {
+        RemoteClient {
+            port,
+            reader: PacketReader::with_max_payload(MAX_DEVICE_PAYLOAD),
+            frames: FrameDecoder::new(),
+            frame: None,
+        }
+    }
+
+    /// Next packet that isn't part of a screenshot
+    fn read_reply(&mut self) -> std::io::Result<(u8, Vec<u8>)> {
+        loop {
+            let (kind, payload) = self.reader.read_packet(&mut self.port)?;
+            if (OK..=ERROR).contains(&kind) {
+                return Ok((kind, payload));
+            }
+            if let Some(frame) = self.frames.handle(kind, &payload) {
+                self.frame = Some(frame);
+            }
+        }
+    }
+
+    fn command(&mut self, kind: u8, payload: &[u8]) -> std::io::Result<Option<i32>> {
+        link::write_packet(&mut self.port, kind, payload)?;
+        match self.read_reply()? {
+            (OK, _) => Ok(None),
+            (VALUE, payload) => match payload[..] {
+                [a, b, c, d] => Ok(Some(i32::from_le_bytes([a, b, c, d]))),
+                _ => Err(protocol_error(format!("malformed value {payload:?}"))),
+            },
+            (_, payload) => Err(protocol_error(format!("device error {payload:?}"))),
+        }
+    }
+
+    fn pointer(&mut self, kind: u8, x: f32, y: f32) -> std::io::Result<()> {
+        self.command(kind, &[x.to_le_bytes(), y.to_le_bytes()].concat())
+            .map(drop)
+    }
+
+    /// Capture the screen
+    pub fn screenshot(&mut self) -> std::io::Result<Frame> {
+        self.frame = None;
+        self.command(SCREENSHOT, &[])?;
+        loop {
+            if let Some(frame) = self.frame.take() {
+                return Ok(frame);
+            }
+            let (kind, payload) = self.reader.read_packet(&mut self.port)?;
+            self.frame = self.frames.handle(kind, &payload);
+        }
+    }
+}
+
+impl<P: Read + Write> ScriptTarget for RemoteClient<P> {
+    fn press(&mut self, x: f32, y: f32) -> std::io::Result<()> {
+        self.pointer(PRESS, x, y)
+    }
+
+    fn move_to(&mut self, x: f32, y: f32) -> std::io::Result<()> {
+        self.pointer(MOVE, x, y)
+    }
+
+    fn release(&mut self, x: f32, y: f32) -> std::io::Result<()> {
+        self.pointer(RELEASE, x, y)
+    }
+
+    fn key(&mut self, text: &str) -> std::io::Result<()> {
+        self.command(KEY, text.as_bytes()).map(drop)
+    }
+
+    fn wait(&mut self, duration: std::time::Duration) -> std::io::Result<()> {
+        std::thread::sleep(duration);
+        Ok(())
+    }
+
+    fn wait_idle(&mut self) -> std::io::Result<()> {
+        self.command(WAIT_IDLE, &[]).map(drop)
+    }
+
+    fn query(&mut self, property: Property) -> std::io::Result<i32> {
+        self.command(QUERY, &[property as u8])?
+            .ok_or_else(|| protocol_error(format!("no value for {property:?}")))
+    }
+
+    fn screenshot(&mut self, path: &Path) -> std::io::Result<()> {
+        let frame = RemoteClient::screenshot(self)?;
+        crate::write_png_sized(path, frame.width as u32, frame.height as u32, &frame.to_rgb8())
+    }
+}
@@ -0,0 +1,175 @@
+//! Touch scripts driving the simulator or a device
+//!
+//! One command per line, `#` starts a comment. Coordinates are in logical pixels, durations in milliseconds.
+//!
+//! ```text
+//! tap 30 90                # press and release
+//! press 200 100
+//! move 200 60
+//! release 200 60
+//! key Tab                  # a named key, or the text of the key
+//! wait 500                 # let timers and animations run
+//! idle                     # wait for the animations to finish
+//! expect active-page 1     # fail unless the property has this value
+//! screenshot home.png      # relative to the output directory
+//! ```
+//!
+//! The properties are the ones of [`Property`], the key names are the ones of `slint::platform::Key`.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use slint::platform::Key;
+
+use crate::remote::Property;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Tap(f32, f32),
+    Press(f32, f32),
+    Move(f32, f32),
+    Release(f32, f32),
+    Key(String),
+    Wait(Duration),
+    Idle,
+    Expect(Property, i32),
+    Screenshot(PathBuf),
+}
+
+/// What a script runs on, the [`Simulator`](crate::Simulator) or a [`RemoteClient`](crate::remote::RemoteClient)
+pub trait ScriptTarget {
+    fn press(&mut self, x: f32, y: f32) -> std::io::Result<()>;
+    fn move_to(&mut self, x: f32, y: f32) -> std::io::Result<()>;
+    fn release(&mut self, x: f32, y: f32) -> std::io::Result<()>;
+    /// Press and release a key
+    fn key(&mut self, text: &str) -> std::io::Result<()>;
+    fn wait(&mut self, duration: Duration) -> std::io::Result<()>;
+    fn wait_idle(&mut self) -> std::io::Result<()>;
+    fn query(&mut self, property: Property) -> std::io::Result<i32>;
+    fn screenshot(&mut self, path: &Path) -> std::io::Result<()>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn key_text(name: &str) -> Option<String> {
+    let key = match name {
+        "Backspace" => Key::Backspace,
+        "Tab" => Key::Tab,
+        "Return" => Key::Return,
+        "Escape" => Key::Escape,
+        "Backtab" => Key::Backtab,
+        "Delete" => Key::Delete,
+        "Shift" => Key::Shift,
+        "Space" => Key::Space,
+        "Left" => Key::LeftArrow,
+        "Right" => Key::RightArrow,
+        "Up" => Key::UpArrow,
+        "Down" => Key::DownArrow,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        _ => {
+            let mut chars = name.chars();
+            return chars.next().filter(|_| chars.next().is_none()).map(String::from);
+        }
+    };
+    Some(char::from(key).into())
+}
+
+pub fn parse(script: &str) -> Result<Vec<Command>, ParseError> {
+    let mut commands = Vec::new();
+    for (idx, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: String| ParseError { line: idx + 1, message };
+
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap();
+        let args: Vec<&str> = words.collect();
+        let point = || -> Result<(f32, f32), ParseError> {
+            match args[..] {
+                [x, y] => Ok((
+                    x.parse().map_err(|_| error(format!("invalid x coordinate `{x}`")))?,
+                    y.parse().map_err(|_| error(format!("invalid y coordinate `{y}`")))?,
+                )),
+                _ => Err(error(format!("`{name}` takes x and y coordinates"))),
+            }
+        };
+
+        let command = match name {
+            "tap" => point().map(|(x, y)| Command::Tap(x, y))?,
+            "press" => point().map(|(x, y)| Command::Press(x, y))?,
+            "move" => point().map(|(x, y)| Command::Move(x, y))?,
+            "release" => point().map(|(x, y)| Command::Release(x, y))?,
+            "key" => match args[..] {
+                [key] => Command::Key(key_text(key).ok_or_else(|| error(format!("unknown key `{key}`")))?),
+                _ => return Err(error("`key` takes a key name".into())),
+            },
+            "wait" => match args[..] {
+                [ms] => Command::Wait(Duration::from_millis(
+                    ms.parse().map_err(|_| error(format!("invalid duration `{ms}`")))?,
+                )),
+                _ => return Err(error("`wait` takes a duration in milliseconds".into())),
+            },
+            "idle" if args.is_empty() => Command::Idle,
+            "expect" => match args[..] {
+                [property, value] => Command::Expect(
+                    Property::from_name(property).ok_or_else(|| error(format!("unknown property `{property}`")))?,
+                    value.parse().map_err(|_| error(format!("invalid value `{value}`")))?,
+                ),
+                _ => return Err(error("`expect` takes a property and a value".into())),
+            },
+            "screenshot" => match args[..] {
+                [path] => Command::Screenshot(path.into()),
+                _ => return Err(error("`screenshot` takes a file name".into())),
+            },
+            _ => return Err(error(format!("unknown command `{line}`"))),
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+/// Run `commands` on `target`, screenshots are written relative to `out_dir`
+///
+/// Stops at the first failed command or expectation.
+pub fn run(target: &mut impl ScriptTarget, commands: &[Command], out_dir: &Path) -> std::io::Result<()> {
+    for command in commands {
+        match command {
+            Command::Tap(x, y) => {
+                target.press(*x, *y)?;
+                target.wait(Duration::from_millis(50))?;
+                target.release(*x, *y)?;
+            }
+            Command::Press(x, y) => target.press(*x, *y)?,
+            Command::Move(x, y) => target.move_to(*x, *y)?,
+            Command::Release(x, y) => target.release(*x, *y)?,
+            Command::Key(text) => target.key(text)?,
+            Command::Wait(duration) => target.wait(*duration)?,
+            Command::Idle => target.wait_idle()?,
+            Command::Expect(property, expected) => {
+                let value = target.query(*property)?;
+                if value != *expected {
+                    return Err(std::io::Error::other(format!(
+                        "{property:?} is {value}, expected {expected}"
+                    )));
+                }
+            }
+            Command::Screenshot(path) => target.screenshot(&out_dir.join(path))?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+//! The hardware independent parts of the touch input of the firmware, in `src/touch`
+
+#[path = "../../src/touch/event.rs"]
+mod event;
+#[path = "../../src/touch/filter.rs"]
+pub mod filter;
+#[path = "../../src/touch/gesture.rs"]
+pub mod gesture;
+#[path = "../../src/touch/transform.rs"]
+mod transform;
+
+pub use event::EventType;
+pub use transform::{CalibrationPoint, TouchTransform};
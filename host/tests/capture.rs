@@ -0,0 +1,57 @@
+//! Screenshot packet decoding, with packets built the way `src/screenshot/mod.rs` sends them
+
+use hpm_slint_host::capture::{FrameDecoder, BEGIN, END, LINE};
+use hpm_slint_host::link::packet;
+
+fn line(y: u16, x: u16, pixels: &[u16]) -> Vec<u8> {
+    let mut payload = [y.to_le_bytes(), x.to_le_bytes()].concat();
+    payload.extend(pixels.iter().flat_map(|p| p.to_le_bytes()));
+    packet(LINE, &payload)
+}
+
+fn begin(width: u16, height: u16) -> Vec<u8> {
+    packet(
+        BEGIN,
+        &[width.to_le_bytes(), height.to_le_bytes(), [0, 0]].concat()[..5],
+    )
+}
+
+#[test]
+fn reassembles_frame_from_line_segments() {
+    let mut stream = b"boot log noise".to_vec();
+    stream.extend(begin(3, 2));
+    stream.extend(line(0, 0, &[1, 2, 3]));
+    stream.extend(line(1, 1, &[5, 6]));
+    stream.extend(packet(END, &2u16.to_le_bytes()));
+
+    let mut decoder = FrameDecoder::new();
+    // Byte by byte, like a slow serial port
+    let frames: Vec<_> = stream.chunks(1).filter_map(|b| decoder.push(b)).collect();
+    assert_eq!(frames.len(), 1);
+    assert_eq!((frames[0].width, frames[0].height), (3, 2));
+    assert_eq!(frames[0].pixels, [1, 2, 3, 0, 5, 6]);
+    assert_eq!(decoder.errors(), 0);
+}
+
+#[test]
+fn skips_corrupted_packets() {
+    let mut corrupted = line(1, 0, &[9, 9]);
+    corrupted[6] ^= 0xFF;
+
+    let mut stream = begin(2, 2);
+    stream.extend(line(0, 0, &[1, 2]));
+    stream.extend(corrupted);
+    stream.extend(packet(END, &2u16.to_le_bytes()));
+
+    let mut decoder = FrameDecoder::new();
+    let frame = decoder.push(&stream).unwrap();
+    assert_eq!(frame.pixels, [1, 2, 0, 0]);
+    assert_eq!(decoder.errors(), 1);
+}
+
+#[test]
+fn ignores_lines_outside_of_a_frame() {
+    let mut decoder = FrameDecoder::new();
+    assert_eq!(decoder.push(&line(0, 0, &[1])), None);
+    assert_eq!(decoder.push(&packet(END, &1u16.to_le_bytes())), None);
+}
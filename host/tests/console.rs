@@ -0,0 +1,86 @@
+//! Console decoding, and typing a job title into the simulator as a terminal would
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use hpm_slint_host::console::ConsoleDecoder;
+use hpm_slint_host::{PrinterQueue, Simulator};
+use slint::platform::Key;
+use slint::{ComponentHandle, SharedString};
+
+/// Window position of the "Scan" action of the home page, it doesn't ask for copies
+const SCAN_ACTION: (f32, f32) = (100.0, 183.0);
+
+fn decode(bytes: &[u8]) -> Vec<SharedString> {
+    let mut decoder = ConsoleDecoder::new();
+    let mut keys = Vec::new();
+    for byte in bytes {
+        decoder.push(*byte, |text| keys.push(text));
+    }
+    keys.extend(decoder.timeout());
+    keys
+}
+
+#[test]
+fn decodes_text_and_escape_sequences() {
+    let key = |key: Key| SharedString::from(key);
+    assert_eq!(decode(b"ab\r"), ["a".into(), "b".into(), key(Key::Return)]);
+    assert_eq!(
+        decode(b"\x7f\x08\t"),
+        [key(Key::Backspace), key(Key::Backspace), key(Key::Tab)]
+    );
+    assert_eq!(
+        decode(b"\x1b[A\x1b[B\x1b[C\x1b[D"),
+        [
+            key(Key::UpArrow),
+            key(Key::DownArrow),
+            key(Key::RightArrow),
+            key(Key::LeftArrow)
+        ]
+    );
+    assert_eq!(
+        decode(b"\x1b[H\x1b[4~\x1b[3~\x1b[Z"),
+        [key(Key::Home), key(Key::End), key(Key::Delete), key(Key::Backtab)]
+    );
+    // Modifiers are ignored
+    assert_eq!(decode(b"\x1b[1;5C"), [key(Key::RightArrow)]);
+    // UTF-8 is decoded, the font only has ASCII and the ellipsis
+    assert_eq!(decode("a…".as_bytes()), [SharedString::from("a"), "…".into()]);
+}
+
+#[test]
+fn lone_escape_completes_on_timeout() {
+    let mut decoder = ConsoleDecoder::new();
+    let mut keys = Vec::new();
+    decoder.push(0x1b, |text| keys.push(text));
+    assert!(keys.is_empty());
+    assert_eq!(decoder.timeout(), Some(Key::Escape.into()));
+    assert_eq!(decoder.timeout(), None);
+
+    // Not a sequence, both keys are typed
+    assert_eq!(decode(b"\x1bx"), [Key::Escape.into(), SharedString::from("x")]);
+}
+
+#[test]
+fn job_title_typed_on_console() {
+    let mut sim = Simulator::new();
+    let started = Rc::new(RefCell::new(Vec::new()));
+    let started_copy = started.clone();
+    sim.main_window()
+        .global::<PrinterQueue>()
+        .on_start_job(move |title| started_copy.borrow_mut().push(title));
+
+    sim.tap(SCAN_ACTION.0, SCAN_ACTION.1);
+    sim.wait_idle();
+    // The title is prefilled with the action and selected, erase it
+    sim.type_console(b"\x7fReport2\x1b[D \x1b[F\r");
+    sim.wait_idle();
+    assert_eq!(*started.borrow(), ["Report 2"]);
+
+    // Escape dismisses the dialog without starting a job
+    sim.tap(SCAN_ACTION.0, SCAN_ACTION.1);
+    sim.wait_idle();
+    sim.type_console(b"Draft\x1b");
+    sim.wait_idle();
+    assert_eq!(started.borrow().len(), 1);
+}
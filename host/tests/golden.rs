@@ -0,0 +1,159 @@
+//! Golden-image tests of the printer demo UI
+//!
+//! Every frame is compared against `tests/golden/<name>.png`. Run with `UPDATE_GOLDEN=1` to write the references
+//! after an intended UI change, and review the new images before committing them. On a mismatch the rendered frame is
+//! saved next to the build artifacts, the path is in the failure message.
+
+use std::path::PathBuf;
+
+use hpm_slint_host::{
+    write_png, DemoPalette, InkLevel, JobStatus, PrinterQueue, PrinterQueueItem, Simulator, HEIGHT, WIDTH,
+};
+use slint::ComponentHandle;
+
+/// Largest per-channel difference of two pixels still considered equal, covers anti-aliasing changes
+const CHANNEL_TOLERANCE: u8 = 8;
+/// Share of the pixels allowed to differ by more than [`CHANNEL_TOLERANCE`]
+const MAX_DIFFERENT_PIXELS: f32 = 0.001;
+
+/// Simulator with fixed ink levels and print queue, so the frames don't depend on the `.slint` defaults
+fn simulator() -> Simulator {
+    let sim = Simulator::new();
+    let main_window = sim.main_window();
+    main_window.set_ink_levels(
+        [
+            InkLevel {
+                color: slint::Color::from_rgb_u8(0, 255, 255),
+                level: 0.40,
+            },
+            InkLevel {
+                color: slint::Color::from_rgb_u8(255, 0, 255),
+                level: 0.20,
+            },
+            InkLevel {
+                color: slint::Color::from_rgb_u8(255, 255, 0),
+                level: 0.50,
+            },
+            InkLevel {
+                color: slint::Color::from_rgb_u8(0, 0, 0),
+                level: 0.80,
+            },
+        ]
+        .into(),
+    );
+    let job = |status, progress, title: &str| PrinterQueueItem {
+        status,
+        progress,
+        title: title.into(),
+        owner: "golden@test".into(),
+        pages: 3,
+        size: "96kB".into(),
+        submission_date: "09:30 01/02/24".into(),
+    };
+    main_window.global::<PrinterQueue>().set_printer_queue(
+        [
+            job(JobStatus::Printing, 42, "report.pdf"),
+            job(JobStatus::Waiting, 0, "photo.png"),
+            job(JobStatus::Waiting, 0, "letter.docx"),
+        ]
+        .into(),
+    );
+    sim
+}
+
+fn show(sim: &mut Simulator, page: i32, night_mode: bool) {
+    sim.main_window().global::<DemoPalette>().set_night_mode(night_mode);
+    sim.main_window().set_active_page(page);
+    // Let the page switch animations settle, the ink levels take the longest
+    sim.wait_idle();
+}
+
+fn read_png(path: &PathBuf) -> Option<Vec<u8>> {
+    let decoder = png::Decoder::new(std::fs::File::open(path).ok()?);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!(
+        (
+            info.width as usize,
+            info.height as usize,
+            info.color_type,
+            info.bit_depth
+        ),
+        (WIDTH, HEIGHT, png::ColorType::Rgb, png::BitDepth::Eight),
+        "{} is not a {WIDTH}x{HEIGHT} 8-bit RGB image",
+        path.display()
+    );
+    buf.truncate(info.buffer_size());
+    Some(buf)
+}
+
+fn assert_golden(sim: &mut Simulator, name: &str) {
+    let actual = sim.render_rgb8();
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(golden.parent().unwrap()).unwrap();
+        write_png(&golden, &actual).unwrap();
+        return;
+    }
+    let Some(expected) = read_png(&golden) else {
+        panic!("missing {}, run with UPDATE_GOLDEN=1 to create it", golden.display());
+    };
+
+    let different = actual
+        .chunks(3)
+        .zip(expected.chunks(3))
+        .filter(|(a, e)| a.iter().zip(e.iter()).any(|(a, e)| a.abs_diff(*e) > CHANNEL_TOLERANCE))
+        .count();
+    if different as f32 > MAX_DIFFERENT_PIXELS * (WIDTH * HEIGHT) as f32 {
+        let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.actual.png"));
+        write_png(&out, &actual).unwrap();
+        panic!(
+            "{name}: {different} pixels differ from {}, the rendered frame is {}",
+            golden.display(),
+            out.display()
+        );
+    }
+}
+
+#[test]
+fn home_page() {
+    let mut sim = simulator();
+    show(&mut sim, 0, false);
+    assert_golden(&mut sim, "home");
+}
+
+#[test]
+fn settings_page() {
+    let mut sim = simulator();
+    show(&mut sim, 1, false);
+    assert_golden(&mut sim, "settings");
+}
+
+#[test]
+fn ink_page() {
+    let mut sim = simulator();
+    show(&mut sim, 2, false);
+    assert_golden(&mut sim, "ink");
+}
+
+#[test]
+fn night_mode() {
+    let mut sim = simulator();
+    for (page, name) in [(0, "home-night"), (1, "settings-night"), (2, "ink-night")] {
+        show(&mut sim, page, true);
+        assert_golden(&mut sim, name);
+    }
+}
+
+#[test]
+fn night_mode_toggle_restores_day_colors() {
+    let mut sim = simulator();
+    show(&mut sim, 1, true);
+    sim.render();
+    show(&mut sim, 1, false);
+    assert_golden(&mut sim, "settings");
+}
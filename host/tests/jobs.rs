@@ -0,0 +1,142 @@
+//! Job submission protocol, between the client and a stand-in of the device on the other end of a socket pair
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::thread::JoinHandle;
+
+use hpm_slint_host::job_protocol::{self, FrameReader, Message, NotificationQueue, Request};
+use hpm_slint_host::jobs::JobClient;
+use hpm_slint_host::printer_queue::{JobInfo, PrinterQueue, Status, Step};
+
+/// Serves the requests like the firmware does, each request also prints the first job halfway, then to completion
+fn stand_in_device(mut port: UnixStream) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut queue = PrinterQueue::new();
+        let mut reader = FrameReader::new();
+        let mut buf = [0; 64];
+        let mut half_done = false;
+        loop {
+            let n = match port.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => n,
+            };
+            reader.push(&buf[..n]);
+            while let Some(request) = reader.next_frame::<Request>() {
+                let reply = match request {
+                    Ok(request) => job_protocol::handle(request, &mut queue),
+                    Err(_) => Message::Invalid,
+                };
+                port.write_all(&job_protocol::encode(&reply)).unwrap();
+
+                let step = if half_done { Step::Completed } else { Step::Progress(50) };
+                let event = queue.tick(|_, _| step);
+                if let Some(notification) = Message::from_tick(&event) {
+                    half_done = !half_done;
+                    port.write_all(&job_protocol::encode(&notification)).unwrap();
+                }
+            }
+        }
+    })
+}
+
+fn job(title: &str, pages: u32) -> JobInfo {
+    JobInfo {
+        title: title.into(),
+        owner: "tester".into(),
+        pages,
+        size: "12kB".into(),
+        submission_date: "10:00 01/02/24".into(),
+    }
+}
+
+#[test]
+fn submit_list_cancel_and_notifications() {
+    let (host, device) = UnixStream::pair().unwrap();
+    let device = stand_in_device(device);
+    let mut client = JobClient::new(host);
+
+    let report = client.submit(job("report.pdf", 3)).unwrap();
+    let notes = client.submit(job("notes.txt", 1)).unwrap();
+    assert_ne!(report, notes);
+
+    // The notifications sent while waiting for the replies are kept
+    assert_eq!(
+        client.next_notification().unwrap(),
+        Message::Progress {
+            id: report,
+            progress: 50
+        }
+    );
+    assert_eq!(client.next_notification().unwrap(), Message::Completed { id: report });
+
+    let jobs = client.list().unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].id, notes);
+    assert_eq!(jobs[0].info, job("notes.txt", 1));
+    assert_eq!(jobs[0].status, Status::Waiting);
+
+    assert!(client.cancel(notes).unwrap());
+    assert!(!client.cancel(notes).unwrap());
+    assert!(client.list().unwrap().is_empty());
+
+    drop(client);
+    device.join().unwrap();
+}
+
+#[test]
+fn garbage_is_rejected_and_skipped() {
+    let (mut host, device) = UnixStream::pair().unwrap();
+    let device = stand_in_device(device);
+
+    // Not a valid request, then an empty frame, then a valid one
+    host.write_all(&[0x03, 0xFF, 0xFF, 0x00, 0x00]).unwrap();
+    host.write_all(&job_protocol::encode(&Request::List)).unwrap();
+
+    let mut reader = FrameReader::new();
+    let mut replies = Vec::new();
+    let mut buf = [0; 64];
+    while replies.len() < 2 {
+        let n = host.read(&mut buf).unwrap();
+        reader.push(&buf[..n]);
+        while let Some(message) = reader.next_frame::<Message>() {
+            replies.push(message.unwrap());
+        }
+    }
+    assert_eq!(replies, [Message::Invalid, Message::Jobs(Vec::new())]);
+
+    drop(host);
+    device.join().unwrap();
+}
+
+#[test]
+fn notifications_keep_the_terminal_events() {
+    let mut queue = NotificationQueue::new(3);
+    for progress in [10, 20, 30] {
+        queue.push(Message::Progress { id: 1, progress });
+    }
+    // The progress of a job that is still waiting is updated
+    assert_eq!(queue.len(), 1);
+
+    queue.push(Message::Completed { id: 1 });
+    queue.push(Message::Progress { id: 2, progress: 50 });
+    // Full, the oldest progress makes room
+    queue.push(Message::Failed { id: 2 });
+    queue.push(Message::Completed { id: 3 });
+    queue.push(Message::Completed { id: 4 });
+    // Full of terminal events, the progress is dropped and the terminal events are kept past the capacity
+    queue.push(Message::Progress { id: 5, progress: 10 });
+    queue.push(Message::Failed { id: 5 });
+
+    let sent: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(
+        sent,
+        [
+            Message::Completed { id: 1 },
+            Message::Failed { id: 2 },
+            Message::Completed { id: 3 },
+            Message::Completed { id: 4 },
+            Message::Failed { id: 5 },
+        ]
+    );
+    assert!(queue.is_empty());
+}
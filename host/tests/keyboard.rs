@@ -0,0 +1,72 @@
+//! Entering jobs with the on-screen keyboard
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use hpm_slint_host::{PrinterQueue, Simulator};
+use slint::ComponentHandle;
+
+/// Window positions of the actions of the home page
+const COPY_ACTION: (f32, f32) = (100.0, 92.0);
+const SCAN_ACTION: (f32, f32) = (100.0, 183.0);
+/// Plus button of the copies spin box of the job dialog
+const MORE_COPIES: (f32, f32) = (499.0, 71.0);
+
+/// Window position of a key, by row and position in the row of the letters layout
+fn key(row: usize, index: usize) -> (f32, f32) {
+    let y = [124.0, 156.0, 188.0, 220.0][row];
+    let x = match row {
+        0 => 34.0 + 52.0 * index as f32,
+        1 => 60.0 + 52.0 * index as f32,
+        _ => 112.0 + 52.0 * index as f32,
+    };
+    (x, y)
+}
+
+const DEL: (f32, f32) = (484.0, 188.0);
+const SHIFT: (f32, f32) = (52.0, 188.0);
+const DIGITS: (f32, f32) = (88.0, 220.0);
+const ENTER: (f32, f32) = (448.0, 220.0);
+
+fn tap(sim: &mut Simulator, (x, y): (f32, f32)) {
+    sim.tap(x, y);
+    sim.wait_idle();
+}
+
+#[test]
+fn scan_job_typed_on_keyboard() {
+    let mut sim = Simulator::new();
+    let started = Rc::new(RefCell::new(Vec::new()));
+    let started_copy = started.clone();
+    sim.main_window()
+        .global::<PrinterQueue>()
+        .on_start_job(move |title| started_copy.borrow_mut().push(title));
+
+    tap(&mut sim, SCAN_ACTION);
+    // The prefilled title is selected
+    tap(&mut sim, DEL);
+    // Shift applies to the next key only
+    tap(&mut sim, SHIFT);
+    tap(&mut sim, key(1, 5)); // h
+    tap(&mut sim, key(0, 7)); // i
+    tap(&mut sim, DIGITS);
+    tap(&mut sim, key(0, 1)); // 2
+    tap(&mut sim, ENTER);
+    assert_eq!(*started.borrow(), ["Hi2"]);
+}
+
+#[test]
+fn copy_job_asks_for_copies() {
+    let mut sim = Simulator::new();
+    let started = Rc::new(RefCell::new(Vec::new()));
+    let started_copy = started.clone();
+    sim.main_window()
+        .global::<PrinterQueue>()
+        .on_start_copy_job(move |title, copies| started_copy.borrow_mut().push((title, copies)));
+
+    tap(&mut sim, COPY_ACTION);
+    tap(&mut sim, MORE_COPIES);
+    tap(&mut sim, MORE_COPIES);
+    tap(&mut sim, ENTER);
+    assert_eq!(*started.borrow(), [("Copy".into(), 3)]);
+}
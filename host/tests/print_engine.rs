@@ -0,0 +1,98 @@
+//! Print engine of the firmware: speed, resuming and running out of ink
+
+use std::time::Duration;
+
+use hpm_slint_host::print_engine::{PrintEngine, PrintEvent, PrintSettings, PrintStep};
+use hpm_slint_host::settings::Quality;
+
+fn settings(quality: Quality, turbo: bool) -> PrintSettings {
+    PrintSettings { quality, turbo }
+}
+
+#[test]
+fn pages_per_minute_follow_quality_and_turbo() {
+    for (quality, normal, turbo) in [
+        (Quality::Best, 8.0, 12.0),
+        (Quality::Medium, 15.0, 22.5),
+        (Quality::Draft, 24.0, 36.0),
+    ] {
+        assert_eq!(settings(quality, false).pages_per_minute(), normal, "{quality:?}");
+        assert_eq!(
+            settings(quality, true).pages_per_minute(),
+            turbo,
+            "{quality:?} with TURBO"
+        );
+    }
+
+    // A page of draft takes 2.5 s
+    let mut engine = PrintEngine::new(settings(Quality::Draft, false), vec![1.0]);
+    engine.start_job(2, 0);
+    let half = Duration::from_millis(2500);
+    assert_eq!(
+        engine.run(half),
+        PrintStep {
+            progress: 50,
+            event: None
+        }
+    );
+    assert_eq!(
+        engine.run(half),
+        PrintStep {
+            progress: 100,
+            event: Some(PrintEvent::JobCompleted),
+        }
+    );
+}
+
+#[test]
+fn job_resumes_from_its_progress() {
+    let mut engine = PrintEngine::new(settings(Quality::Draft, false), vec![1.0]);
+    engine.start_job(4, 50);
+    assert_eq!(
+        engine.run(Duration::from_millis(2500)),
+        PrintStep {
+            progress: 75,
+            event: None
+        }
+    );
+    // Never prints more pages than the job has
+    assert_eq!(
+        engine.run(Duration::from_secs(60)),
+        PrintStep {
+            progress: 100,
+            event: Some(PrintEvent::JobCompleted),
+        }
+    );
+    assert!(
+        (engine.ink_levels()[0] - 0.998).abs() < 1e-6,
+        "{:?}",
+        engine.ink_levels()
+    );
+}
+
+#[test]
+fn empty_cartridge_stops_the_job() {
+    // A page of best quality uses 0.003 of a cartridge
+    let mut engine = PrintEngine::new(settings(Quality::Best, false), vec![1.0, 0.0025]);
+    engine.start_job(10, 0);
+    let page = Duration::from_millis(7500);
+    assert_eq!(
+        engine.run(page),
+        PrintStep {
+            progress: 10,
+            event: Some(PrintEvent::OutOfInk(1)),
+        }
+    );
+    assert_eq!(engine.empty_ink(), Some(1));
+
+    // Nothing more is printed or used
+    let levels = engine.ink_levels().to_vec();
+    assert_eq!(
+        engine.run(page),
+        PrintStep {
+            progress: 10,
+            event: Some(PrintEvent::OutOfInk(1)),
+        }
+    );
+    assert_eq!(engine.ink_levels(), levels);
+}
@@ -0,0 +1,147 @@
+//! Print queue logic of the firmware
+
+use hpm_slint_host::printer_queue::{JobInfo, PrinterQueue, Status, Step, TickEvent};
+
+fn job(title: &str) -> JobInfo {
+    JobInfo {
+        title: title.into(),
+        pages: 2,
+        ..Default::default()
+    }
+}
+
+fn queue(titles: &[&str]) -> PrinterQueue {
+    let mut queue = PrinterQueue::new();
+    for title in titles {
+        queue.push(job(title));
+    }
+    queue
+}
+
+fn titles(queue: &PrinterQueue) -> Vec<&str> {
+    queue.jobs().iter().map(|job| job.info.title.as_str()).collect()
+}
+
+fn statuses(queue: &PrinterQueue) -> Vec<Status> {
+    queue.jobs().iter().map(|job| job.status).collect()
+}
+
+/// Run one tick with `step` as outcome, returns the event and the printed job with whether it was starting
+fn tick(queue: &mut PrinterQueue, step: Step) -> (TickEvent, Option<(String, bool)>) {
+    let mut printed = None;
+    let event = queue.tick(|job, starting| {
+        printed = Some((job.info.title.clone(), starting));
+        step
+    });
+    (event, printed)
+}
+
+#[test]
+fn prints_in_order() {
+    let mut queue = queue(&["a", "b"]);
+    let ids: Vec<u32> = queue.jobs().iter().map(|job| job.id).collect();
+    assert_ne!(ids[0], ids[1]);
+
+    let (event, printed) = tick(&mut queue, Step::Progress(40));
+    assert_eq!(
+        event,
+        TickEvent::Progress {
+            id: ids[0],
+            progress: 40
+        }
+    );
+    assert_eq!(printed, Some(("a".into(), true)));
+    assert_eq!(statuses(&queue), [Status::Printing, Status::Waiting]);
+
+    let (_, printed) = tick(&mut queue, Step::Progress(80));
+    assert_eq!(printed, Some(("a".into(), false)));
+
+    let (event, _) = tick(&mut queue, Step::Completed);
+    match event {
+        TickEvent::Completed(job) => {
+            assert_eq!(job.id, ids[0]);
+            assert_eq!(job.progress, 100);
+        }
+        event => panic!("unexpected {event:?}"),
+    }
+    assert_eq!(titles(&queue), ["b"]);
+
+    let (_, printed) = tick(&mut queue, Step::Completed);
+    assert_eq!(printed, Some(("b".into(), true)));
+    assert!(queue.is_empty());
+    assert_eq!(tick(&mut queue, Step::Completed), (TickEvent::Idle, None));
+}
+
+#[test]
+fn cancelling_the_printing_job_starts_the_next() {
+    let mut queue = queue(&["a", "b"]);
+    tick(&mut queue, Step::Progress(50));
+
+    let cancelled = queue.cancel(0).unwrap();
+    assert_eq!(cancelled.info.title, "a");
+    assert_eq!(cancelled.status, Status::Cancelled);
+    assert_eq!(cancelled.progress, 50);
+
+    let (_, printed) = tick(&mut queue, Step::Progress(10));
+    assert_eq!(printed, Some(("b".into(), true)));
+}
+
+#[test]
+fn out_of_range_indices_are_ignored() {
+    let mut queue = queue(&["a"]);
+    assert_eq!(queue.cancel(1), None);
+    assert_eq!(queue.cancel(usize::MAX), None);
+    assert!(!queue.toggle_pause(1));
+    assert!(!queue.move_job(0, 1));
+    assert!(!queue.move_job(1, 0));
+    assert_eq!(titles(&queue), ["a"]);
+
+    let mut empty = PrinterQueue::new();
+    assert_eq!(empty.cancel(0), None);
+    assert!(!empty.toggle_pause(0));
+}
+
+#[test]
+fn paused_jobs_are_skipped_and_resume_where_they_were() {
+    let mut queue = queue(&["a", "b"]);
+    tick(&mut queue, Step::Progress(30));
+
+    assert!(queue.toggle_pause(0));
+    assert_eq!(statuses(&queue), [Status::Paused, Status::Waiting]);
+    let (_, printed) = tick(&mut queue, Step::Progress(20));
+    assert_eq!(printed, Some(("b".into(), true)));
+
+    // The job being printed goes on, the resumed one waits for it
+    assert!(queue.toggle_pause(0));
+    assert_eq!(queue.jobs()[0].progress, 30);
+    let (_, printed) = tick(&mut queue, Step::Completed);
+    assert_eq!(printed, Some(("b".into(), false)));
+    let (event, printed) = tick(&mut queue, Step::Progress(60));
+    assert_eq!(printed, Some(("a".into(), true)));
+    assert!(matches!(event, TickEvent::Progress { progress: 60, .. }));
+}
+
+#[test]
+fn failed_jobs_stay_and_are_skipped() {
+    let mut queue = queue(&["a", "b"]);
+    let id = queue.jobs()[0].id;
+    assert_eq!(tick(&mut queue, Step::Failed).0, TickEvent::Failed { id });
+    assert_eq!(statuses(&queue), [Status::Error, Status::Waiting]);
+    // Failed jobs can't be paused
+    assert!(!queue.toggle_pause(0));
+
+    let (_, printed) = tick(&mut queue, Step::Progress(10));
+    assert_eq!(printed, Some(("b".into(), true)));
+}
+
+#[test]
+fn reorder() {
+    let mut queue = queue(&["a", "b", "c"]);
+    assert!(queue.move_job(0, 2));
+    assert_eq!(titles(&queue), ["b", "c", "a"]);
+    assert!(queue.move_job(2, 0));
+    assert_eq!(titles(&queue), ["a", "b", "c"]);
+    let id = queue.jobs()[1].id;
+    assert!(queue.move_job(1, 1));
+    assert_eq!(queue.position(id), Some(1));
+}
@@ -0,0 +1,55 @@
+//! Slint model of the print queue, as the job server of the firmware drives it
+
+use std::rc::Rc;
+use std::time::Duration;
+
+use hpm_slint_host::job_protocol::{Message, Request};
+use hpm_slint_host::printer_queue::{self, JobInfo, Step, TickEvent};
+use hpm_slint_host::queue_model::PrinterQueueData;
+use hpm_slint_host::remote::Property;
+use hpm_slint_host::{JobStatus, PrinterQueue, Simulator};
+use slint::{ComponentHandle, Model as _};
+
+/// Period of the print timer of the firmware
+const PRINT_PERIOD: Duration = Duration::from_millis(100);
+
+#[test]
+fn job_submitted_while_idle_starts_printing() {
+    let mut simulator = Simulator::new();
+    let printer_queue = Rc::new(PrinterQueueData::new(printer_queue::PrinterQueue::new()));
+    simulator
+        .main_window()
+        .global::<PrinterQueue>()
+        .set_printer_queue(printer_queue.data.clone().into());
+    // Like the firmware, every tick prints half of the job and the timer stops once there is nothing to print
+    let printer_queue_weak = Rc::downgrade(&printer_queue);
+    printer_queue
+        .print_progress_timer
+        .start(slint::TimerMode::Repeated, PRINT_PERIOD, move || {
+            let printer_queue = printer_queue_weak.upgrade().unwrap();
+            let event = printer_queue.queue.borrow_mut().tick(|_, _| Step::Progress(50));
+            if event == TickEvent::Idle {
+                printer_queue.print_progress_timer.stop();
+            }
+            printer_queue.sync();
+        });
+    simulator.advance(PRINT_PERIOD);
+    assert!(!printer_queue.print_progress_timer.running());
+    assert_eq!(simulator.query(Property::QueueLength), 0);
+
+    // The job server posts the request to the idle loop, which runs it like this
+    let reply = printer_queue.handle(Request::Submit(JobInfo {
+        title: "remote.pdf".into(),
+        pages: 2,
+        ..Default::default()
+    }));
+    assert!(matches!(reply, Message::Submitted { .. }), "{reply:?}");
+    assert_eq!(simulator.query(Property::QueueLength), 1);
+    assert!(printer_queue.print_progress_timer.running());
+
+    simulator.advance(PRINT_PERIOD);
+    let item = printer_queue.data.row_data(0).unwrap();
+    assert_eq!(item.title, "remote.pdf");
+    assert_eq!(item.status, JobStatus::Printing);
+    assert_eq!(item.progress, 50);
+}
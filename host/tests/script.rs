@@ -0,0 +1,98 @@
+//! Touch scripts, run in the simulator and against a scripted stand-in of the device
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use hpm_slint_host::capture::{BEGIN, END, LINE};
+use hpm_slint_host::link::{packet, PacketReader};
+use hpm_slint_host::remote::{self, Property, RemoteClient};
+use hpm_slint_host::script::{self, Command, ScriptTarget};
+use hpm_slint_host::Simulator;
+
+#[test]
+fn parse_reports_line_of_error() {
+    let error = script::parse("# comment\n\ntap 1 2\nexpect active-page\n").unwrap_err();
+    assert_eq!(error.line, 4);
+
+    assert!(script::parse("key NoSuchKey").is_err());
+    assert!(script::parse("tap 1").is_err());
+    assert_eq!(
+        script::parse("key Tab  # focus\nkey a\nidle").unwrap(),
+        [Command::Key("\t".into()), Command::Key("a".into()), Command::Idle]
+    );
+}
+
+#[test]
+fn sidebar_navigation_in_simulator() {
+    let commands = script::parse(
+        "expect active-page 0
+         tap 29 102
+         idle
+         expect active-page 1
+         tap 29 152
+         idle
+         expect active-page 2
+         tap 29 52
+         idle
+         expect active-page 0",
+    )
+    .unwrap();
+    script::run(&mut Simulator::new(), &commands, Path::new(".")).unwrap();
+}
+
+#[test]
+fn failed_expectation_stops_the_script() {
+    let commands = script::parse("expect active-page 2\ntap 29 102").unwrap();
+    let mut sim = Simulator::new();
+    assert!(script::run(&mut sim, &commands, Path::new(".")).is_err());
+    assert_eq!(sim.query(Property::ActivePage), 0);
+}
+
+/// Stand-in of the device, answers the commands with canned packets
+#[derive(Default)]
+struct FakeDevice {
+    received: PacketReader,
+    output: VecDeque<u8>,
+}
+
+impl Write for FakeDevice {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.received.push(buf);
+        while let Some((kind, _)) = self.received.next_packet() {
+            let reply = match kind {
+                remote::QUERY => packet(remote::VALUE, &7i32.to_le_bytes()),
+                remote::SCREENSHOT => [
+                    packet(remote::OK, &[]),
+                    packet(BEGIN, &[1, 0, 1, 0, 0]),
+                    packet(LINE, &[0, 0, 0, 0, 0x34, 0x12]),
+                    packet(END, &[1, 0]),
+                ]
+                .concat(),
+                _ => packet(remote::OK, &[]),
+            };
+            self.output.extend(reply);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for FakeDevice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.output.read(buf)
+    }
+}
+
+#[test]
+fn remote_client_encodes_commands() {
+    let mut client = RemoteClient::new(FakeDevice::default());
+    client.press(1.0, 2.0).unwrap();
+    client.key("\t").unwrap();
+    assert_eq!(client.query(Property::QueueLength).unwrap(), 7);
+    let frame = client.screenshot().unwrap();
+    assert_eq!((frame.width, frame.height, frame.pixels), (1, 1, vec![0x1234]));
+}
@@ -0,0 +1,242 @@
+//! Settings persistence of the firmware, on a flash simulated in memory
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash};
+use hpm_slint_host::settings::{ColorMode, Layout, Quality, Settings, SettingsStore, SLOT_SIZE};
+
+const SECTOR_SIZE: usize = 4096;
+/// Where the settings area starts, after a sector of something else
+const AREA: u32 = SECTOR_SIZE as u32;
+const AREA_LEN: u32 = 2 * SECTOR_SIZE as u32;
+
+struct Memory {
+    bytes: Vec<u8>,
+    erase_counts: Vec<u32>,
+}
+
+/// NOR flash in memory, writes can only clear bits. Clones share the memory, like a reset keeps the flash.
+#[derive(Clone)]
+struct RamFlash(Rc<RefCell<Memory>>);
+
+impl RamFlash {
+    fn new() -> Self {
+        RamFlash(Rc::new(RefCell::new(Memory {
+            bytes: vec![0xFF; 3 * SECTOR_SIZE],
+            erase_counts: vec![0; 3],
+        })))
+    }
+
+    fn erase_counts(&self) -> Vec<u32> {
+        self.0.borrow().erase_counts.clone()
+    }
+
+    fn corrupt(&self, offset: u32) {
+        self.0.borrow_mut().bytes[offset as usize] ^= 0x10;
+    }
+
+    /// Rewrite the sequence number of the record in `slot`, as if the counter had come that far
+    fn set_sequence(&self, slot: u32, sequence: u32) {
+        let mut memory = self.0.borrow_mut();
+        let record = &mut memory.bytes[(AREA + slot * SLOT_SIZE as u32) as usize..][..SLOT_SIZE];
+        record[4..8].copy_from_slice(&sequence.to_le_bytes());
+        let end = 8 + record[3] as usize;
+        let crc = crc32(&record[..end]);
+        record[end..end + 4].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    fn store(&self) -> SettingsStore<RamFlash> {
+        SettingsStore::new(self.clone(), AREA, AREA_LEN)
+    }
+}
+
+impl ErrorType for RamFlash {
+    type Error = NorFlashErrorKind;
+}
+
+impl ReadNorFlash for RamFlash {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let memory = self.0.borrow();
+        let range = offset as usize..offset as usize + bytes.len();
+        bytes.copy_from_slice(memory.bytes.get(range).ok_or(NorFlashErrorKind::OutOfBounds)?);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.0.borrow().bytes.len()
+    }
+}
+
+impl NorFlash for RamFlash {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let (from, to) = (from as usize, to as usize);
+        if from % SECTOR_SIZE != 0 || to % SECTOR_SIZE != 0 {
+            return Err(NorFlashErrorKind::NotAligned);
+        }
+        let mut memory = self.0.borrow_mut();
+        memory.bytes[from..to].fill(0xFF);
+        for sector in from / SECTOR_SIZE..to / SECTOR_SIZE {
+            memory.erase_counts[sector] += 1;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let mut memory = self.0.borrow_mut();
+        let offset = offset as usize;
+        for (cell, byte) in memory.bytes[offset..offset + bytes.len()].iter_mut().zip(bytes) {
+            *cell &= *byte;
+        }
+        Ok(())
+    }
+}
+
+/// CRC-32 (IEEE) of the records
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn changed(n: u32) -> Settings {
+    Settings {
+        night_mode: n % 2 == 1,
+        quality: [Quality::Best, Quality::Medium, Quality::Draft][n as usize % 3],
+        eco_mode: n % 5 == 0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn blank_flash_has_no_settings() {
+    let flash = RamFlash::new();
+    assert_eq!(flash.store().load(), Ok(None));
+}
+
+#[test]
+fn saved_settings_are_loaded_after_a_reset() {
+    let flash = RamFlash::new();
+    let settings = Settings {
+        night_mode: true,
+        layout: Layout::Landscape,
+        quality: Quality::Draft,
+        color: ColorMode::Color,
+        eco_mode: true,
+        turbo: false,
+        // The default transform of the panel, exact in fixed point
+        touch_calibration: Some([[0.0, 1.0, 0.0], [-1.0, 0.0, 240.0]]),
+    };
+    let mut store = flash.store();
+    store.load().unwrap();
+    store.save(&Settings::default()).unwrap();
+    store.save(&settings).unwrap();
+
+    assert_eq!(flash.store().load(), Ok(Some(settings)));
+    // Nothing outside of the area was touched
+    assert_eq!(flash.erase_counts()[0], 0);
+}
+
+#[test]
+fn writes_are_spread_over_the_area() {
+    let flash = RamFlash::new();
+    let slots = AREA_LEN / SLOT_SIZE as u32;
+    // Reset after every save
+    for n in 0..4 * slots {
+        let mut store = flash.store();
+        store.load().unwrap();
+        store.save(&changed(n)).unwrap();
+    }
+    assert_eq!(flash.store().load(), Ok(Some(changed(4 * slots - 1))));
+    // Every sector was erased once per pass over the area
+    assert_eq!(flash.erase_counts(), [0, 4, 4]);
+}
+
+#[test]
+fn corrupted_record_falls_back_to_the_previous_one() {
+    let flash = RamFlash::new();
+    let mut store = flash.store();
+    store.load().unwrap();
+    store.save(&changed(1)).unwrap();
+    store.save(&changed(2)).unwrap();
+
+    // A reset while the second record was written
+    flash.corrupt(AREA + SLOT_SIZE as u32 + 9);
+    let mut store = flash.store();
+    assert_eq!(store.load(), Ok(Some(changed(1))));
+
+    // The damaged slot is skipped, it can't be written before the sector is erased
+    store.save(&changed(3)).unwrap();
+    assert_eq!(flash.store().load(), Ok(Some(changed(3))));
+}
+
+#[test]
+fn previous_sector_survives_until_the_next_record_is_written() {
+    let flash = RamFlash::new();
+    let slots_per_sector = (SECTOR_SIZE / SLOT_SIZE) as u32;
+    let mut store = flash.store();
+    store.load().unwrap();
+    for n in 0..slots_per_sector {
+        store.save(&changed(n)).unwrap();
+    }
+    assert_eq!(flash.erase_counts(), [0, 1, 0]);
+
+    // The first save in the second sector erases it, the first sector is left alone
+    store.save(&changed(slots_per_sector)).unwrap();
+    assert_eq!(flash.erase_counts(), [0, 1, 1]);
+    flash.corrupt(AREA + SECTOR_SIZE as u32 + 9);
+    assert_eq!(flash.store().load(), Ok(Some(changed(slots_per_sector - 1))));
+}
+
+#[test]
+fn sequence_number_wraps_around() {
+    let flash = RamFlash::new();
+    let mut store = flash.store();
+    store.load().unwrap();
+    store.save(&changed(1)).unwrap();
+    store.save(&changed(2)).unwrap();
+    // The counter wrapped between the two records
+    flash.set_sequence(0, u32::MAX);
+    flash.set_sequence(1, 0);
+
+    let mut store = flash.store();
+    assert_eq!(store.load(), Ok(Some(changed(2))));
+    store.save(&changed(3)).unwrap();
+    assert_eq!(flash.store().load(), Ok(Some(changed(3))));
+}
+
+#[test]
+fn touch_calibration_is_rounded_to_fixed_point() {
+    let flash = RamFlash::new();
+    let calibration = [[0.01234, -1.1, 3.33], [0.9, 0.00001, -245.678]];
+    let mut store = flash.store();
+    store.load().unwrap();
+    store
+        .save(&Settings {
+            touch_calibration: Some(calibration),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let loaded = flash.store().load().unwrap().unwrap().touch_calibration.unwrap();
+    for (row, saved_row) in loaded.iter().zip(&calibration) {
+        for (i, (value, saved)) in row.iter().zip(saved_row).enumerate() {
+            let precision = if i == 2 { 1.0 / 16.0 } else { 1.0 / 8192.0 };
+            assert!((value - saved).abs() <= precision / 2.0, "{value} != {saved}");
+        }
+    }
+}
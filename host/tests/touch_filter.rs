@@ -0,0 +1,119 @@
+//! Touch filtering of the firmware, fed with the samples of a poll every 10 ms
+
+use embassy_time::{Duration, Instant};
+use hpm_slint_host::touch::filter::{FilterConfig, Smoothing, TouchEvent, TouchFilter};
+use hpm_slint_host::touch::EventType;
+use slint::LogicalPosition;
+
+const POLL: u64 = 10;
+
+fn at(x: f32, y: f32) -> LogicalPosition {
+    LogicalPosition::new(x, y)
+}
+
+/// Runs a filter on a clock advancing by a poll at every sample
+struct Panel {
+    filter: TouchFilter,
+    now: Instant,
+}
+
+impl Panel {
+    fn new(config: FilterConfig) -> Self {
+        Panel {
+            filter: TouchFilter::new(config),
+            now: Instant::from_millis(1000),
+        }
+    }
+
+    fn poll(&mut self, sample: Option<(EventType, LogicalPosition)>) -> Option<TouchEvent> {
+        self.now += Duration::from_millis(POLL);
+        self.filter.update(self.now, sample)
+    }
+
+    fn contact(&mut self, x: f32, y: f32) -> Option<TouchEvent> {
+        self.poll(Some((EventType::Contact, at(x, y))))
+    }
+
+    fn press(&mut self, x: f32, y: f32) -> Option<TouchEvent> {
+        self.poll(Some((EventType::PressDown, at(x, y))))
+    }
+
+    fn lift(&mut self, x: f32, y: f32) -> Option<TouchEvent> {
+        self.poll(Some((EventType::LiftUp, at(x, y))))
+    }
+
+    /// Polls with nothing reported for `ms`, returns the events
+    fn idle(&mut self, ms: u64) -> Vec<TouchEvent> {
+        (0..ms / POLL).filter_map(|_| self.poll(None)).collect()
+    }
+}
+
+fn unsmoothed() -> FilterConfig {
+    FilterConfig {
+        smoothing: Smoothing::None,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn jitter_within_the_tap_slop_is_dropped() {
+    let mut panel = Panel::new(unsmoothed());
+    assert_eq!(panel.press(100.0, 100.0), Some(TouchEvent::Pressed(at(100.0, 100.0))));
+    for (x, y) in [(102.0, 99.0), (97.0, 103.0), (104.0, 100.0)] {
+        assert_eq!(panel.contact(x, y), None);
+    }
+    // Released where it was pressed, the jitter didn't move the pointer
+    assert_eq!(panel.lift(103.0, 98.0), Some(TouchEvent::Released(at(100.0, 100.0))));
+}
+
+#[test]
+fn moves_past_the_tap_slop_are_forwarded() {
+    let mut panel = Panel::new(unsmoothed());
+    panel.press(100.0, 100.0);
+    assert_eq!(panel.contact(110.0, 100.0), Some(TouchEvent::Moved(at(110.0, 100.0))));
+    // Once dragging, small moves are forwarded too
+    assert_eq!(panel.contact(111.0, 100.0), Some(TouchEvent::Moved(at(111.0, 100.0))));
+    assert_eq!(panel.lift(111.0, 100.0), Some(TouchEvent::Released(at(111.0, 100.0))));
+}
+
+#[test]
+fn moving_average_smooths_the_moves() {
+    let mut panel = Panel::new(FilterConfig {
+        smoothing: Smoothing::MovingAverage { window: 2 },
+        tap_slop: 0.0,
+        ..Default::default()
+    });
+    panel.press(100.0, 100.0);
+    assert_eq!(panel.contact(120.0, 100.0), Some(TouchEvent::Moved(at(110.0, 100.0))));
+    assert_eq!(panel.contact(120.0, 110.0), Some(TouchEvent::Moved(at(120.0, 105.0))));
+}
+
+#[test]
+fn stuck_contact_is_released_after_the_timeout() {
+    let config = unsmoothed();
+    let mut panel = Panel::new(config);
+    panel.press(100.0, 100.0);
+    // The controller stops reporting without a lift up, e.g. a missed interrupt
+    let timeout = config.release_timeout.as_millis();
+    assert_eq!(panel.idle(timeout - POLL), []);
+    assert!(panel.filter.is_pressed());
+    assert_eq!(panel.idle(POLL), [TouchEvent::Released(at(100.0, 100.0))]);
+    assert!(!panel.filter.is_pressed());
+    assert_eq!(panel.idle(timeout), []);
+}
+
+#[test]
+fn release_is_debounced() {
+    let config = unsmoothed();
+    let mut panel = Panel::new(config);
+    panel.press(100.0, 100.0);
+    assert_eq!(panel.lift(100.0, 100.0), Some(TouchEvent::Released(at(100.0, 100.0))));
+    // A bounce right after the release is not a new press
+    assert_eq!(panel.contact(100.0, 100.0), None);
+    assert!(!panel.filter.is_pressed());
+    // Lifts while not pressed are ignored
+    assert_eq!(panel.lift(100.0, 100.0), None);
+
+    panel.idle(config.debounce.as_millis());
+    assert_eq!(panel.contact(50.0, 60.0), Some(TouchEvent::Pressed(at(50.0, 60.0))));
+}
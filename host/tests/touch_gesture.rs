@@ -0,0 +1,96 @@
+//! Tap classification of the firmware, fed with the filtered events of a poll every 10 ms
+
+use embassy_time::{Duration, Instant};
+use hpm_slint_host::touch::filter::TouchEvent;
+use hpm_slint_host::touch::gesture::{GestureConfig, TapClassifier, TapGesture};
+use slint::LogicalPosition;
+
+const POLL: u64 = 10;
+
+fn at(x: f32, y: f32) -> LogicalPosition {
+    LogicalPosition::new(x, y)
+}
+
+/// Runs a classifier on a clock advancing by a poll at every event
+struct Panel {
+    classifier: TapClassifier,
+    now: Instant,
+}
+
+impl Panel {
+    fn new(config: GestureConfig) -> Self {
+        Panel {
+            classifier: TapClassifier::new(config),
+            now: Instant::from_millis(1000),
+        }
+    }
+
+    fn poll(&mut self, event: Option<TouchEvent>) -> Option<TapGesture> {
+        self.now += Duration::from_millis(POLL);
+        self.classifier.update(self.now, event)
+    }
+
+    /// Polls without new events for `ms`, returns the gestures
+    fn hold(&mut self, ms: u64) -> Vec<TapGesture> {
+        (0..ms / POLL).filter_map(|_| self.poll(None)).collect()
+    }
+
+    /// Press, stay for `ms` and release at the same position, returns the gestures
+    fn tap(&mut self, position: LogicalPosition, ms: u64) -> Vec<TapGesture> {
+        let mut gestures: Vec<_> = self.poll(Some(TouchEvent::Pressed(position))).into_iter().collect();
+        gestures.extend(self.hold(ms));
+        gestures.extend(self.poll(Some(TouchEvent::Released(position))));
+        gestures
+    }
+}
+
+#[test]
+fn short_taps_are_left_to_slint() {
+    let mut panel = Panel::new(GestureConfig::default());
+    // Single and double taps are clicks and double clicks for Slint, there is nothing to report
+    assert_eq!(panel.tap(at(100.0, 100.0), 50), []);
+    assert_eq!(panel.tap(at(105.0, 95.0), 50), []);
+}
+
+#[test]
+fn long_press_then_hold_repeats() {
+    let config = GestureConfig::default();
+    let long_press = config.long_press.as_millis();
+    let repeat = config.hold_repeat.unwrap().as_millis();
+    let mut panel = Panel::new(config);
+    let position = at(50.0, 60.0);
+
+    panel.poll(Some(TouchEvent::Pressed(position)));
+    assert_eq!(panel.hold(long_press - POLL), []);
+    assert_eq!(panel.hold(POLL), [TapGesture::LongPress(position)]);
+    assert_eq!(panel.hold(2 * repeat), [TapGesture::Hold(position); 2]);
+    // The release reports nothing, the bridge forwards it to Slint
+    assert_eq!(panel.poll(Some(TouchEvent::Released(position))), None);
+    assert_eq!(panel.tap(position, 50), []);
+    // The next touch starts over
+    assert_eq!(panel.tap(position, long_press), [TapGesture::LongPress(position)]);
+}
+
+#[test]
+fn hold_repeat_can_be_disabled() {
+    let config = GestureConfig {
+        hold_repeat: None,
+        ..Default::default()
+    };
+    let mut panel = Panel::new(config);
+    let gestures = panel.tap(at(50.0, 60.0), 3 * config.long_press.as_millis());
+    assert_eq!(gestures, [TapGesture::LongPress(at(50.0, 60.0))]);
+}
+
+#[test]
+fn drags_are_neither_taps_nor_long_presses() {
+    let config = GestureConfig::default();
+    let mut panel = Panel::new(config);
+    let position = at(100.0, 100.0);
+
+    // The filter only reports a move once the finger left its tap slop
+    panel.poll(Some(TouchEvent::Pressed(position)));
+    panel.poll(Some(TouchEvent::Moved(at(120.0, 100.0))));
+    assert_eq!(panel.hold(2 * config.long_press.as_millis()), []);
+    assert_eq!(panel.poll(Some(TouchEvent::Released(at(120.0, 100.0)))), None);
+}
@@ -0,0 +1,91 @@
+//! Touch calibration of the firmware
+
+use hpm_slint_host::touch::{CalibrationPoint, TouchTransform};
+
+/// Corners of the screen and its center, the targets of the calibration page
+const TARGETS: [(f32, f32); 5] = [
+    (40.0, 40.0),
+    (496.0, 40.0),
+    (496.0, 200.0),
+    (40.0, 200.0),
+    (268.0, 120.0),
+];
+
+fn assert_close(a: TouchTransform, b: TouchTransform) {
+    for (row_a, row_b) in a.m.iter().zip(&b.m) {
+        for (a, b) in row_a.iter().zip(row_b) {
+            assert!((a - b).abs() < 1e-3, "{a:?} != {b:?}");
+        }
+    }
+}
+
+/// The calibration points of a panel whose raw coordinates map to the screen through `transform`
+fn points(transform: TouchTransform) -> Vec<CalibrationPoint> {
+    let inverse = transform.inverse().unwrap();
+    TARGETS
+        .iter()
+        .map(|&(x, y)| CalibrationPoint {
+            screen: (x, y),
+            raw: inverse.apply(x, y),
+        })
+        .collect()
+}
+
+#[test]
+fn identity_is_found_from_three_points() {
+    let found = TouchTransform::from_calibration(&points(TouchTransform::IDENTITY)[..3]).unwrap();
+    assert_close(found, TouchTransform::IDENTITY);
+}
+
+#[test]
+fn swapped_and_mirrored_axes_are_found() {
+    let panel = TouchTransform::default();
+    // The glass reports portrait coordinates for the landscape screen
+    assert_eq!(panel.apply(0.0, 0.0), (0.0, 240.0));
+    assert_eq!(panel.apply(240.0, 536.0), (536.0, 0.0));
+    assert_close(TouchTransform::from_calibration(&points(panel)).unwrap(), panel);
+
+    let skewed = TouchTransform::IDENTITY
+        .swap_xy()
+        .mirror_x(536.0)
+        .scale(1.1, 0.9)
+        .offset(-7.0, 12.0);
+    assert_close(TouchTransform::from_calibration(&points(skewed)).unwrap(), skewed);
+}
+
+#[test]
+fn inverse_undoes_the_transform() {
+    let transform = TouchTransform::default().scale(2.0, 0.5).offset(10.0, -4.0);
+    let inverse = transform.inverse().unwrap();
+    assert_close(transform.then(inverse), TouchTransform::IDENTITY);
+    assert_close(inverse.then(transform), TouchTransform::IDENTITY);
+    let (x, y) = inverse.apply(100.0, 50.0);
+    assert_eq!(transform.apply(x, y), (100.0, 50.0));
+}
+
+#[test]
+fn degenerate_points_are_rejected() {
+    assert_eq!(
+        TouchTransform::from_calibration(&points(TouchTransform::IDENTITY)[..2]),
+        None
+    );
+
+    // All the touches on a line, e.g. the same corner touched every time
+    let collinear: Vec<_> = TARGETS
+        .iter()
+        .enumerate()
+        .map(|(i, &screen)| CalibrationPoint {
+            screen,
+            raw: (10.0 * i as f32, 20.0 + 5.0 * i as f32),
+        })
+        .collect();
+    assert_eq!(TouchTransform::from_calibration(&collinear), None);
+    let same_point = [CalibrationPoint {
+        screen: TARGETS[0],
+        raw: (30.0, 30.0),
+    }; 4];
+    assert_eq!(TouchTransform::from_calibration(&same_point), None);
+
+    // A panel that only reports one axis can't be inverted
+    assert_eq!(TouchTransform::IDENTITY.scale(1.0, 0.0).inverse(), None);
+}
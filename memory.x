@@ -1,7 +1,8 @@
 MEMORY
 {
     XPI0_HEADER : ORIGIN = 0x80000000, LENGTH = 0x3000 /* bootheader */
-    XPI0_APP    : ORIGIN = 0x80003000, LENGTH = 1024K - 0x3000 /* app firmware */
+    XPI0_APP    : ORIGIN = 0x80003000, LENGTH = 1024K - 0x3000 - 8K /* app firmware */
+    XPI0_SETTINGS : ORIGIN = 0x800FE000, LENGTH = 8K /* persistent settings, two sectors, see src/settings.rs */
     /* DLM0        : ORIGIN = 0x00080000, LENGTH =  128K data local memory */
     ILM0        : ORIGIN = 0x00000000, LENGTH =  8K /* instruction local memory */
     DLM0        : ORIGIN = 0x00062000, LENGTH =  256K - 8K /* data local memory */
@@ -15,6 +16,10 @@ REGION_ALIAS("REGION_HEAP", DLM0);
 REGION_ALIAS("REGION_STACK", DLM0);
 REGION_ALIAS("REGION_FASTTEXT", ILM0);
 
+/* Offsets in the XPI0 flash of the settings area */
+__settings_start = ORIGIN(XPI0_SETTINGS) - ORIGIN(XPI0_HEADER);
+__settings_end = __settings_start + LENGTH(XPI0_SETTINGS);
+
 
 /*
 SECTIONS
@@ -0,0 +1,231 @@
+//! GPIO buttons and rotary encoder mapped to Slint key events
+//!
+//! Lets the UI be operated without the touch panel: the keys move the focus (Tab/Backtab), activate the focused
+//! control (Return), change its value (arrows) or cancel (Escape), see the focus handling of the widgets in
+//! `ui/common.slint`.
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use embassy_futures::select::select_slice;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::digital::Wait;
+use slint::platform::{Key, WindowEvent};
+use slint::SharedString;
+
+use crate::slint_ui::{post_window_event, MainWindow};
+
+/// Sampling period while an input is changing
+const POLL_PERIOD: Duration = Duration::from_millis(1);
+/// An encoder left between two detents is considered settled after this long
+const ENCODER_IDLE: Duration = Duration::from_millis(50);
+
+/// Tracks the stable level of a bouncing contact
+#[derive(Copy, Clone, Debug)]
+pub struct Debouncer {
+    period: Duration,
+    stable: bool,
+    last: bool,
+    changed_at: Instant,
+}
+
+impl Debouncer {
+    pub fn new(period: Duration, level: bool) -> Self {
+        Debouncer {
+            period,
+            stable: level,
+            last: level,
+            changed_at: Instant::from_ticks(0),
+        }
+    }
+
+    /// Feed a sample, returns the new stable level once it held for the debounce period
+    pub fn update(&mut self, now: Instant, level: bool) -> Option<bool> {
+        if level != self.last {
+            self.last = level;
+            self.changed_at = now;
+        }
+        if self.last != self.stable && now - self.changed_at >= self.period {
+            self.stable = self.last;
+            return Some(self.stable);
+        }
+        None
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.last == self.stable
+    }
+}
+
+/// A push button, active low
+pub struct Button<P> {
+    pin: P,
+    /// Text of the key event, usually a [`Key`]
+    key: SharedString,
+    debouncer: Debouncer,
+}
+
+impl<P: InputPin> Button<P> {
+    pub fn new(pin: P, key: impl Into<SharedString>) -> Self {
+        Button {
+            pin,
+            key: key.into(),
+            debouncer: Debouncer::new(Duration::from_millis(20), false),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub enum Direction {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// Quadrature decoder, counts the transitions of the A and B phases
+///
+/// Invalid transitions (both phases changing at once) are ignored, which also filters most of the contact bounce.
+#[derive(Copy, Clone, Debug)]
+pub struct QuadratureDecoder {
+    state: u8,
+    steps: i8,
+    steps_per_detent: i8,
+}
+
+impl QuadratureDecoder {
+    /// Step for each transition, indexed by the previous and the new `BA` state
+    const STEPS: [i8; 16] = [0, 1, -1, 0, -1, 0, 0, 1, 1, 0, 0, -1, 0, -1, 1, 0];
+
+    pub fn new(steps_per_detent: i8, a: bool, b: bool) -> Self {
+        QuadratureDecoder {
+            state: (b as u8) << 1 | a as u8,
+            steps: 0,
+            steps_per_detent,
+        }
+    }
+
+    /// Feed a sample of the phases, returns the direction once a whole detent was turned
+    pub fn update(&mut self, a: bool, b: bool) -> Option<Direction> {
+        let state = (b as u8) << 1 | a as u8;
+        self.steps += Self::STEPS[(self.state << 2 | state) as usize];
+        self.state = state;
+        if self.steps >= self.steps_per_detent {
+            self.steps = 0;
+            Some(Direction::Clockwise)
+        } else if self.steps <= -self.steps_per_detent {
+            self.steps = 0;
+            Some(Direction::CounterClockwise)
+        } else {
+            None
+        }
+    }
+
+    /// Between two detents, more transitions are expected
+    pub fn is_settled(&self) -> bool {
+        self.steps == 0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EncoderConfig {
+    pub clockwise: SharedString,
+    pub counter_clockwise: SharedString,
+    /// Transitions per detent, 4 for most encoders
+    pub steps_per_detent: i8,
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        EncoderConfig {
+            clockwise: Key::Tab.into(),
+            counter_clockwise: Key::Backtab.into(),
+            steps_per_detent: 4,
+        }
+    }
+}
+
+/// A rotary encoder, each detent presses and releases a key
+pub struct Encoder<P> {
+    a: P,
+    b: P,
+    config: EncoderConfig,
+    decoder: QuadratureDecoder,
+    phases: (bool, bool),
+    changed_at: Instant,
+}
+
+impl<P: InputPin> Encoder<P> {
+    pub fn new(mut a: P, mut b: P, config: EncoderConfig) -> Self {
+        let phases = (a.is_high().unwrap_or(false), b.is_high().unwrap_or(false));
+        Encoder {
+            a,
+            b,
+            decoder: QuadratureDecoder::new(config.steps_per_detent, phases.0, phases.1),
+            config,
+            phases,
+            changed_at: Instant::from_ticks(0),
+        }
+    }
+}
+
+fn send_key(main_window: &slint::Weak<MainWindow>, text: &SharedString, pressed: bool) {
+    let text = text.clone();
+    let event = if pressed {
+        WindowEvent::KeyPressed { text }
+    } else {
+        WindowEvent::KeyReleased { text }
+    };
+    post_window_event(main_window, event);
+}
+
+/// Translate the inputs into key events, forever
+///
+/// Sleeps until an input changes, then samples them until they settle.
+pub async fn run<P>(mut buttons: Vec<Button<P>>, mut encoder: Option<Encoder<P>>, main_window: slint::Weak<MainWindow>)
+where
+    P: InputPin + Wait,
+{
+    loop {
+        {
+            let pins = buttons.iter_mut().map(|button| &mut button.pin);
+            let pins = pins.chain(encoder.iter_mut().flat_map(|encoder| [&mut encoder.a, &mut encoder.b]));
+            let mut waits: Vec<_> = pins.map(|pin| pin.wait_for_any_edge()).collect();
+            select_slice(&mut waits).await;
+        }
+
+        loop {
+            let now = Instant::now();
+            let mut settled = true;
+            for button in buttons.iter_mut() {
+                let pressed = button.pin.is_low().unwrap_or(false);
+                if let Some(pressed) = button.debouncer.update(now, pressed) {
+                    send_key(&main_window, &button.key, pressed);
+                }
+                settled &= button.debouncer.is_settled();
+            }
+            if let Some(encoder) = encoder.as_mut() {
+                let (a, b) = (
+                    encoder.a.is_high().unwrap_or(false),
+                    encoder.b.is_high().unwrap_or(false),
+                );
+                if (a, b) != encoder.phases {
+                    encoder.phases = (a, b);
+                    encoder.changed_at = now;
+                }
+                if let Some(direction) = encoder.decoder.update(a, b) {
+                    let key = match direction {
+                        Direction::Clockwise => &encoder.config.clockwise,
+                        Direction::CounterClockwise => &encoder.config.counter_clockwise,
+                    };
+                    send_key(&main_window, key, true);
+                    send_key(&main_window, key, false);
+                }
+                settled &= encoder.decoder.is_settled() || now - encoder.changed_at >= ENCODER_IDLE;
+            }
+            if settled {
+                break;
+            }
+            Timer::after(POLL_PERIOD).await;
+        }
+    }
+}
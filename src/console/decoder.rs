@@ -0,0 +1,145 @@
+//! Decoding of the bytes sent by a terminal
+//!
+//! Also built into the host simulator, see `host/tests/console.rs`.
+
+use slint::platform::Key;
+use slint::SharedString;
+
+const ESC: u8 = 0x1B;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum State {
+    Ground,
+    /// After ESC
+    Escape,
+    /// After `ESC [`, with the numeric parameter read so far
+    Csi(u8),
+    /// Inside a multi-byte UTF-8 character
+    Utf8 {
+        len: u8,
+        needed: u8,
+    },
+}
+
+/// Decodes the bytes typed in a terminal into key texts
+pub struct ConsoleDecoder {
+    state: State,
+    utf8: [u8; 4],
+}
+
+impl Default for ConsoleDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleDecoder {
+    pub fn new() -> Self {
+        ConsoleDecoder {
+            state: State::Ground,
+            utf8: [0; 4],
+        }
+    }
+
+    /// Feed one byte, `key` is called with the text of each completed key
+    pub fn push(&mut self, byte: u8, mut key: impl FnMut(SharedString)) {
+        if let Some(text) = self.decode(byte, &mut key) {
+            key(text);
+        }
+    }
+
+    fn decode(&mut self, byte: u8, key: &mut impl FnMut(SharedString)) -> Option<SharedString> {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::Escape if byte == b'[' => {
+                self.state = State::Csi(0);
+                None
+            }
+            // Alt+key is not supported, take it as Escape followed by the key
+            State::Escape => {
+                self.state = State::Ground;
+                key(Key::Escape.into());
+                self.ground(byte)
+            }
+            State::Csi(param) => match byte {
+                b'0'..=b'9' => {
+                    self.state = State::Csi(param.saturating_mul(10).saturating_add(byte - b'0'));
+                    None
+                }
+                // Parameters separated by `;` are modifiers, ignored
+                b';' => None,
+                _ => {
+                    self.state = State::Ground;
+                    let key = match (byte, param) {
+                        (b'A', _) => Key::UpArrow,
+                        (b'B', _) => Key::DownArrow,
+                        (b'C', _) => Key::RightArrow,
+                        (b'D', _) => Key::LeftArrow,
+                        (b'H', _) | (b'~', 1 | 7) => Key::Home,
+                        (b'F', _) | (b'~', 4 | 8) => Key::End,
+                        (b'~', 3) => Key::Delete,
+                        (b'Z', _) => Key::Backtab,
+                        _ => return None,
+                    };
+                    Some(key.into())
+                }
+            },
+            State::Utf8 { len, needed } => {
+                if byte & 0xC0 != 0x80 {
+                    // Broken sequence, start over with this byte
+                    self.state = State::Ground;
+                    return self.ground(byte);
+                }
+                self.utf8[len as usize] = byte;
+                let len = len + 1;
+                if len < needed {
+                    self.state = State::Utf8 { len, needed };
+                    return None;
+                }
+                self.state = State::Ground;
+                core::str::from_utf8(&self.utf8[..len as usize])
+                    .ok()
+                    .map(SharedString::from)
+            }
+        }
+    }
+
+    /// In the middle of a sequence, [`timeout`](Self::timeout) has to be called if nothing follows
+    pub fn is_pending(&self) -> bool {
+        !matches!(self.state, State::Ground)
+    }
+
+    /// Called when no byte followed for a while, completes a lone ESC
+    pub fn timeout(&mut self) -> Option<SharedString> {
+        match core::mem::replace(&mut self.state, State::Ground) {
+            State::Escape => Some(Key::Escape.into()),
+            _ => None,
+        }
+    }
+
+    fn ground(&mut self, byte: u8) -> Option<SharedString> {
+        let key = match byte {
+            ESC => {
+                self.state = State::Escape;
+                return None;
+            }
+            b'\r' | b'\n' => Key::Return,
+            0x08 | 0x7F => Key::Backspace,
+            b'\t' => Key::Tab,
+            0x20..=0x7E => return Some(SharedString::from(core::str::from_utf8(&[byte]).unwrap())),
+            0xC0..=0xF7 => {
+                self.utf8[0] = byte;
+                let needed = match byte {
+                    0xC0..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    _ => 4,
+                };
+                self.state = State::Utf8 { len: 1, needed };
+                return None;
+            }
+            // Other control characters
+            _ => return None,
+        };
+        Some(key.into())
+    }
+}
@@ -0,0 +1,58 @@
+//! Text console on a UART, typed characters are fed to the window as key events
+//!
+//! Any terminal works, e.g. `picocom -b 115200 /dev/ttyUSB1`: printable characters are typed into the focused
+//! `TextInput`, Enter, Backspace, Tab, Escape and the ANSI escape sequences of the arrow, Home, End and Delete keys
+//! are translated to the Slint keys.
+
+use embassy_futures::select::{select, Either};
+use embassy_time::{Duration, Timer};
+use slint::platform::WindowEvent;
+use slint::SharedString;
+
+use crate::slint_ui::{post_window_event, MainWindow};
+
+mod decoder;
+
+pub use decoder::ConsoleDecoder;
+
+/// A lone ESC is the Escape key, unless the rest of a sequence follows within this delay
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Read the console from `rx` and type into the window, forever
+pub async fn run<R>(mut rx: R, main_window: slint::Weak<MainWindow>)
+where
+    R: embedded_io_async::Read,
+{
+    let mut decoder = ConsoleDecoder::new();
+    let mut buf = [0; 32];
+    loop {
+        // Only bounded in the middle of a sequence, otherwise the task sleeps until a byte comes
+        let result = if decoder.is_pending() {
+            match select(rx.read(&mut buf), Timer::after(ESCAPE_TIMEOUT)).await {
+                Either::First(result) => result,
+                Either::Second(()) => {
+                    if let Some(text) = decoder.timeout() {
+                        type_key(&main_window, text);
+                    }
+                    continue;
+                }
+            }
+        } else {
+            rx.read(&mut buf).await
+        };
+
+        match result {
+            Ok(n) => {
+                for byte in &buf[..n] {
+                    decoder.push(*byte, |text| type_key(&main_window, text));
+                }
+            }
+            Err(e) => defmt::warn!("console: read failed: {}", defmt::Debug2Format(&e)),
+        }
+    }
+}
+
+fn type_key(main_window: &slint::Weak<MainWindow>, text: SharedString) {
+    post_window_event(main_window, WindowEvent::KeyPressed { text: text.clone() });
+    post_window_event(main_window, WindowEvent::KeyReleased { text });
+}
@@ -0,0 +1,167 @@
+use embedded_hal::{delay::DelayNs, digital::OutputPin, i2c::I2c};
+
+use crate::touch::{Error, EventType, Gesture, PointEvent, TouchController};
+
+pub const DEFAULT_ADDR: u8 = 0x15;
+
+pub mod regs {
+    pub const GEST_ID: u8 = 0x01;
+    pub const FINGER_NUM: u8 = 0x02;
+    pub const CHIPID: u8 = 0xA7;
+    pub const PROJID: u8 = 0xA8;
+    pub const FWVERSION: u8 = 0xA9;
+    pub const MOTION_MASK: u8 = 0xEC;
+    pub const IRQ_CTL: u8 = 0xFA;
+    pub const DIS_AUTO_SLEEP: u8 = 0xFE;
+}
+
+const CHIPID_CST816S: u8 = 0xB4;
+const CHIPID_CST816T: u8 = 0xB5;
+const CHIPID_CST816D: u8 = 0xB6;
+
+const IRQ_EN_TOUCH: u8 = 0x40;
+const IRQ_EN_CHANGE: u8 = 0x20;
+const IRQ_EN_MOTION: u8 = 0x10;
+
+const MOTION_EN_DCLICK: u8 = 0x01;
+
+pub struct CST816S<I2C> {
+    i2c: I2C,
+    addr: u8,
+}
+
+impl<I2C> CST816S<I2C>
+where
+    I2C: I2c,
+{
+    pub fn new(i2c: I2C) -> Self {
+        CST816S {
+            i2c,
+            addr: DEFAULT_ADDR,
+        }
+    }
+
+    pub fn new_with_addr(i2c: I2C, addr: u8) -> Self {
+        CST816S { i2c, addr }
+    }
+
+    pub fn init(&mut self, config: Config) -> Result<(), Error<I2C::Error>> {
+        let chipid = self.read_reg(regs::CHIPID)?;
+        if !matches!(chipid, CHIPID_CST816S | CHIPID_CST816T | CHIPID_CST816D) {
+            return Err(Error::UnsupportedChip(chipid));
+        }
+
+        {
+            let projid = self.read_reg(regs::PROJID)?;
+            let fw_version = self.read_reg(regs::FWVERSION)?;
+            defmt::info!(
+                "chipid 0x{:02x}, projid 0x{:02x}, fw version 0x{:02x}",
+                chipid,
+                projid,
+                fw_version
+            );
+        }
+
+        self.write_reg(regs::IRQ_CTL, IRQ_EN_TOUCH | IRQ_EN_CHANGE | IRQ_EN_MOTION)?;
+        self.write_reg(regs::MOTION_MASK, if config.double_click { MOTION_EN_DCLICK } else { 0 })?;
+        // The chip stops answering on the bus while it sleeps
+        self.write_reg(regs::DIS_AUTO_SLEEP, if config.auto_sleep { 0x00 } else { 0xFF })?;
+
+        Ok(())
+    }
+
+    pub fn reset<P: OutputPin, D: DelayNs>(&mut self, rst: &mut P, delay: &mut D) -> Result<(), P::Error> {
+        rst.set_low()?;
+        delay.delay_ms(10);
+        rst.set_high()?;
+        delay.delay_ms(50);
+
+        Ok(())
+    }
+
+    /// get the touch point, CST816S reports a single finger only
+    pub fn get_point(&mut self) -> Result<Option<PointEvent>, I2C::Error> {
+        let mut buf = [0u8; 5];
+        self.i2c.write_read(self.addr, &[regs::FINGER_NUM], &mut buf)?;
+
+        let fingers = buf[0];
+        let event = if let Some(event) = EventType::from_u8(buf[1] >> 6) {
+            event
+        } else {
+            return Ok(None);
+        };
+        if fingers == 0 && event != EventType::LiftUp {
+            return Ok(None);
+        }
+
+        let x = (((buf[1] as u16) & 0x0F) << 8) | (buf[2] as u16);
+        let y = (((buf[3] as u16) & 0x0F) << 8) | (buf[4] as u16);
+
+        Ok(Some(PointEvent {
+            x,
+            y,
+            event,
+            weight: 0,
+            area: 0,
+            touch_id: 0,
+        }))
+    }
+
+    /// Get the gesture, CST816S click gestures are not reported here
+    pub fn get_gesture(&mut self) -> Result<Option<Gesture>, I2C::Error> {
+        let gesture = match self.read_reg(regs::GEST_ID)? {
+            0x01 => Some(Gesture::MoveUp),
+            0x02 => Some(Gesture::MoveDown),
+            0x03 => Some(Gesture::MoveLeft),
+            0x04 => Some(Gesture::MoveRight),
+            _ => None,
+        };
+        Ok(gesture)
+    }
+
+    fn read_reg(&mut self, reg_addr: u8) -> Result<u8, I2C::Error> {
+        let mut buf = [0u8];
+        self.i2c.write_read(self.addr, &[reg_addr], &mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    fn write_reg(&mut self, reg_addr: u8, value: u8) -> Result<(), I2C::Error> {
+        self.i2c.write(self.addr, &[reg_addr, value])?;
+
+        Ok(())
+    }
+}
+
+impl<I2C> TouchController for CST816S<I2C>
+where
+    I2C: I2c,
+{
+    type Config = Config;
+    type Error = Error<I2C::Error>;
+
+    fn init(&mut self, config: Config) -> Result<(), Self::Error> {
+        CST816S::init(self, config)
+    }
+
+    fn reset<P: OutputPin, D: DelayNs>(&mut self, rst: &mut P, delay: &mut D) -> Result<(), P::Error> {
+        CST816S::reset(self, rst, delay)
+    }
+
+    fn read_report(&mut self) -> Result<Option<PointEvent>, Self::Error> {
+        Ok(self.get_point()?)
+    }
+
+    fn read_gesture(&mut self) -> Result<Option<Gesture>, Self::Error> {
+        Ok(self.get_gesture()?)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Config {
+    /// Let the chip sleep when the panel is not touched, it stops answering on I2C until the next touch
+    pub auto_sleep: bool,
+    /// Enable double click detection
+    pub double_click: bool,
+}
+
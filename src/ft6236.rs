@@ -1,12 +1,23 @@
 use embedded_hal::{delay::DelayNs, digital::OutputPin, i2c::I2c};
 
+pub use crate::touch::{Error, EventType, Gesture, PointEvent};
+use crate::touch::TouchController;
+
 pub const DEFAULT_ADDR: u8 = 0x38;
 
 pub mod regs {
+    pub const LIB_VER_H: u8 = 0xA1;
+    pub const LIB_VER_L: u8 = 0xA2;
     pub const CHIPID: u8 = 0xA3;
     pub const VENDID: u8 = 0xA8;
     pub const FIRMVERS: u8 = 0xA6;
     pub const THRESHHOLD: u8 = 0x80;
+    pub const CTRL: u8 = 0x86;
+    pub const TIMEENTERMONITOR: u8 = 0x87;
+    pub const PERIODACTIVE: u8 = 0x88;
+    pub const PERIODMONITOR: u8 = 0x89;
+    pub const G_MODE: u8 = 0xA4;
+    pub const PWR_MODE: u8 = 0xA5;
     pub const NUMTOUCHES: u8 = 0x02;
     pub const GEST_ID: u8 = 0x01;
 }
@@ -15,75 +26,34 @@ const CHIPID_FT6206: u8 = 0x06;
 const CHIPID_FT6236: u8 = 0x36;
 const CHIPID_FT6236U: u8 = 0x64;
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-#[repr(u8)]
-pub enum Gesture {
-    MoveUp = 0x10,
-    MoveRight = 0x14,
-    MoveDown = 0x18,
-    MoveLeft = 0x1C,
-    ZoomIn = 0x48,
-    ZoomOut = 0x49,
-}
-
-impl Gesture {
-    pub fn gesture_id(&self) -> u8 {
-        match self {
-            Gesture::MoveUp => 0x10,
-            Gesture::MoveRight => 0x14,
-            Gesture::MoveDown => 0x18,
-            Gesture::MoveLeft => 0x1C,
-            Gesture::ZoomIn => 0x48,
-            Gesture::ZoomOut => 0x49,
-        }
-    }
-
-    fn from_u8(gesture: u8) -> Option<Self> {
-        match gesture {
-            0x10 => Some(Gesture::MoveUp),
-            0x14 => Some(Gesture::MoveRight),
-            0x18 => Some(Gesture::MoveDown),
-            0x1C => Some(Gesture::MoveLeft),
-            0x48 => Some(Gesture::ZoomIn),
-            0x49 => Some(Gesture::ZoomOut),
-            // 0x00 => None,
-            _ => None,
-        }
-    }
-}
-
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
-pub enum EventType {
-    PressDown = 0b00,
-    LiftUp = 0b01,
-    Contact = 0b10,
+pub enum ChipModel {
+    FT6206,
+    FT6236,
+    FT6236U,
 }
 
-impl EventType {
-    fn from_u8(event: u8) -> Option<Self> {
-        match event {
-            0b00 => Some(EventType::PressDown),
-            0b01 => Some(EventType::LiftUp),
-            0b10 => Some(EventType::Contact),
+impl ChipModel {
+    fn from_u8(chipid: u8) -> Option<Self> {
+        match chipid {
+            CHIPID_FT6206 => Some(ChipModel::FT6206),
+            CHIPID_FT6236 => Some(ChipModel::FT6236),
+            CHIPID_FT6236U => Some(ChipModel::FT6236U),
             _ => None,
         }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
-pub struct PointEvent {
-    /// Touch X position
-    pub x: u16,
-    /// Touch Y position
-    pub y: u16,
-    /// Touch event flag
-    pub event: EventType,
-    /// Touch weight, in Px_WEIGHT register
-    pub weight: u8,
-    /// Touch area, in Px_MISC register
-    pub area: u8,
-    /// Touch ID, 0 or 1
-    pub touch_id: u8,
+/// Identification of the touch controller, read by [`FT6236::probe`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, defmt::Format)]
+pub struct ChipInfo {
+    pub chip: ChipModel,
+    /// Panel vendor ID, in FOCALTECH_ID register
+    pub vendor_id: u8,
+    /// Firmware version, in FIRMID register
+    pub firmware_version: u8,
+    /// Library version, in LIB_VER_H and LIB_VER_L registers
+    pub library_version: u16,
 }
 
 pub struct FT6236<I2C> {
@@ -106,27 +76,63 @@ where
         FT6236 { i2c, addr }
     }
 
-    pub fn init(&mut self, config: Config) -> Result<(), I2C::Error> {
+    /// Read the chip identification, fails if the chip is not a FT62xx
+    pub fn probe(&mut self) -> Result<ChipInfo, Error<I2C::Error>> {
         let chipid = self.read_reg(regs::CHIPID)?;
-        if chipid != CHIPID_FT6206 && chipid != CHIPID_FT6236 && chipid != CHIPID_FT6236U {
-            defmt::error!("invalid chipid 0x{:02x}", chipid);
-        }
+        let chip = ChipModel::from_u8(chipid).ok_or(Error::UnsupportedChip(chipid))?;
+
+        let vendor_id = self.read_reg(regs::VENDID)?;
+        let firmware_version = self.read_reg(regs::FIRMVERS)?;
+        let library_version =
+            ((self.read_reg(regs::LIB_VER_H)? as u16) << 8) | (self.read_reg(regs::LIB_VER_L)? as u16);
+
+        Ok(ChipInfo {
+            chip,
+            vendor_id,
+            firmware_version,
+            library_version,
+        })
+    }
 
-        {
-            let vendid = self.read_reg(regs::VENDID)?;
-            let active_report_rate = self.read_reg(0x88)?;
-            let monitor_report_rate = self.read_reg(0x89)?;
-            let g_mode = self.read_reg(0xa4)?;
+    /// Probe the chip and apply `config`
+    pub fn init(&mut self, config: Config) -> Result<ChipInfo, Error<I2C::Error>> {
+        let info = self.probe()?;
 
-            defmt::info!("chipid 0x{:02x}, vendid 0x{:02x}", chipid, vendid);
-            defmt::info!("active report rate: 0x{:02x}", active_report_rate);
-            defmt::info!("monitor report rate: 0x{:02x}", monitor_report_rate);
-            defmt::info!("interrupt mode: 0x{:02x}", g_mode);
+        self.write_reg_verified(regs::THRESHHOLD, config.threshhold)?;
+        if let Some(rate) = config.active_report_rate {
+            self.write_reg_verified(regs::PERIODACTIVE, rate)?;
+        }
+        if let Some(rate) = config.monitor_report_rate {
+            self.write_reg_verified(regs::PERIODMONITOR, rate)?;
+        }
+        if let Some(mode) = config.interrupt_mode {
+            self.write_reg_verified(regs::G_MODE, mode as u8)?;
+        }
+        match config.auto_monitor_timeout {
+            None => {}
+            Some(0) => self.write_reg_verified(regs::CTRL, 0x00)?,
+            Some(secs) => {
+                self.write_reg_verified(regs::TIMEENTERMONITOR, secs)?;
+                self.write_reg_verified(regs::CTRL, 0x01)?;
+            }
+        }
+        // Power mode goes last, the chip stops answering once it is in hibernate
+        if let Some(mode) = config.power_mode {
+            self.set_power_mode(mode)?;
         }
 
-        self.write_reg(regs::THRESHHOLD, config.threshhold)?;
+        Ok(info)
+    }
 
-        Ok(())
+    /// Set the power mode of the touch controller.
+    ///
+    /// Once in [`PowerMode::Hibernate`], the chip only wakes up through a hardware reset, see [`FT6236::reset`].
+    pub fn set_power_mode(&mut self, mode: PowerMode) -> Result<(), Error<I2C::Error>> {
+        if mode == PowerMode::Hibernate {
+            Ok(self.write_reg(regs::PWR_MODE, mode as u8)?)
+        } else {
+            self.write_reg_verified(regs::PWR_MODE, mode as u8)
+        }
     }
 
     pub fn reset<P: OutputPin, D: DelayNs>(
@@ -217,16 +223,96 @@ where
 
         Ok(())
     }
+
+    /// Write a register, then read it back to make sure the chip took the value
+    fn write_reg_verified(&mut self, reg_addr: u8, value: u8) -> Result<(), Error<I2C::Error>> {
+        self.write_reg(reg_addr, value)?;
+        let readback = self.read_reg(reg_addr)?;
+        if readback != value {
+            return Err(Error::Verify {
+                reg: reg_addr as u16,
+                wrote: value,
+                read: readback,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// How the INT pin reports touches, in G_MODE register
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[repr(u8)]
+pub enum InterruptMode {
+    /// INT is held low while a touch is present
+    Polling = 0x00,
+    /// INT pulses once for every new report
+    Trigger = 0x01,
+}
+
+/// Power mode, in PWR_MODE register
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[repr(u8)]
+pub enum PowerMode {
+    Active = 0x00,
+    /// Scan at the monitor report rate until a touch is detected
+    Monitor = 0x01,
+    /// Lowest power, only a reset wakes the chip up
+    Hibernate = 0x03,
+}
+
+impl<I2C> TouchController for FT6236<I2C>
+where
+    I2C: I2c,
+{
+    type Config = Config;
+    type Error = Error<I2C::Error>;
+
+    fn init(&mut self, config: Config) -> Result<(), Self::Error> {
+        let info = FT6236::init(self, config)?;
+        defmt::info!("{:?}", info);
+        Ok(())
+    }
+
+    fn reset<P: OutputPin, D: DelayNs>(&mut self, rst: &mut P, delay: &mut D) -> Result<(), P::Error> {
+        FT6236::reset(self, rst, delay)
+    }
+
+    fn read_report(&mut self) -> Result<Option<PointEvent>, Self::Error> {
+        Ok(self.get_point0()?)
+    }
+
+    fn read_gesture(&mut self) -> Result<Option<Gesture>, Self::Error> {
+        Ok(self.get_gesture()?)
+    }
 }
 
+/// Operating parameters written by [`FT6236::init`], the ones left at `None` keep the value of the chip
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Config {
     /// threshold for touch detection
     pub threshhold: u8,
+    /// Report rate in active mode, in PERIODACTIVE register
+    pub active_report_rate: Option<u8>,
+    /// Report rate in monitor mode, in PERIODMONITOR register
+    pub monitor_report_rate: Option<u8>,
+    /// Interrupt or polling trigger mode
+    pub interrupt_mode: Option<InterruptMode>,
+    /// Seconds without touch before switching to monitor mode automatically, `Some(0)` keeps the chip active
+    pub auto_monitor_timeout: Option<u8>,
+    /// Power mode applied at the end of `init`
+    pub power_mode: Option<PowerMode>,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Config { threshhold: 0x40 }
+        Config {
+            threshhold: 0x40,
+            active_report_rate: None,
+            monitor_report_rate: None,
+            interrupt_mode: None,
+            auto_monitor_timeout: None,
+            power_mode: None,
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,170 @@
+use embedded_hal::{delay::DelayNs, digital::OutputPin, i2c::I2c};
+
+use crate::touch::{Error, EventType, PointEvent, TouchController};
+
+/// Address selected when INT is held low during reset
+pub const DEFAULT_ADDR: u8 = 0x5D;
+/// Address selected when INT is held high during reset
+pub const ALT_ADDR: u8 = 0x14;
+
+pub mod regs {
+    pub const COMMAND: u16 = 0x8040;
+    pub const PRODUCT_ID: u16 = 0x8140;
+    pub const FIRMWARE_VERSION: u16 = 0x8144;
+    pub const STATUS: u16 = 0x814E;
+    pub const POINT1: u16 = 0x814F;
+}
+
+const PRODUCT_ID_GT911: &[u8; 3] = b"911";
+
+const STATUS_BUFFER_READY: u8 = 0x80;
+
+pub struct GT911<I2C> {
+    i2c: I2C,
+    addr: u8,
+    /// Last reported point, GT911 has no event flag so press and lift are derived from it
+    last_point: Option<PointEvent>,
+}
+
+impl<I2C> GT911<I2C>
+where
+    I2C: I2c,
+{
+    pub fn new(i2c: I2C) -> Self {
+        GT911 {
+            i2c,
+            addr: DEFAULT_ADDR,
+            last_point: None,
+        }
+    }
+
+    pub fn new_with_addr(i2c: I2C, addr: u8) -> Self {
+        GT911 {
+            i2c,
+            addr,
+            last_point: None,
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), Error<I2C::Error>> {
+        let mut product_id = [0u8; 4];
+        // INT floats during reset, so the chip may answer on either address: try the other one if it doesn't
+        if self.read_regs(regs::PRODUCT_ID, &mut product_id).is_err() {
+            self.addr = if self.addr == DEFAULT_ADDR {
+                ALT_ADDR
+            } else {
+                DEFAULT_ADDR
+            };
+            defmt::info!("no answer, trying address 0x{:02x}", self.addr);
+            self.read_regs(regs::PRODUCT_ID, &mut product_id)?;
+        }
+        if &product_id[..3] != PRODUCT_ID_GT911 {
+            return Err(Error::UnsupportedProduct(product_id));
+        }
+
+        {
+            let mut buf = [0u8; 6];
+            self.read_regs(regs::FIRMWARE_VERSION, &mut buf)?;
+            let fw_version = u16::from_le_bytes([buf[0], buf[1]]);
+            let x_res = u16::from_le_bytes([buf[2], buf[3]]);
+            let y_res = u16::from_le_bytes([buf[4], buf[5]]);
+            defmt::info!("fw version 0x{:04x}, resolution {}x{}", fw_version, x_res, y_res);
+        }
+
+        // Back to coordinate reading mode
+        self.write_reg(regs::COMMAND, 0x00)?;
+        self.write_reg(regs::STATUS, 0x00)?;
+
+        Ok(())
+    }
+
+    /// Reset with INT left floating, the chip keeps the address from the last reset
+    pub fn reset<P: OutputPin, D: DelayNs>(&mut self, rst: &mut P, delay: &mut D) -> Result<(), P::Error> {
+        rst.set_low()?;
+        delay.delay_ms(10); // min = 100us
+        rst.set_high()?;
+        delay.delay_ms(60); // min = 50ms before the first command
+
+        Ok(())
+    }
+
+    /// get first touch point
+    pub fn get_point0(&mut self) -> Result<Option<PointEvent>, I2C::Error> {
+        let status = self.read_reg(regs::STATUS)?;
+        if status & STATUS_BUFFER_READY == 0 {
+            // No new report yet, keep the last state
+            return Ok(self.last_point.map(|p| PointEvent {
+                event: EventType::Contact,
+                ..p
+            }));
+        }
+
+        let touches = status & 0x0F;
+        let point = if touches > 0 {
+            let mut buf = [0u8; 8];
+            self.read_regs(regs::POINT1, &mut buf)?;
+            let size = u16::from_le_bytes([buf[5], buf[6]]);
+            Some(PointEvent {
+                x: u16::from_le_bytes([buf[1], buf[2]]),
+                y: u16::from_le_bytes([buf[3], buf[4]]),
+                event: if self.last_point.is_some() {
+                    EventType::Contact
+                } else {
+                    EventType::PressDown
+                },
+                weight: 0,
+                area: size.min(0x0F) as u8,
+                touch_id: buf[0],
+            })
+        } else {
+            self.last_point.map(|p| PointEvent {
+                event: EventType::LiftUp,
+                ..p
+            })
+        };
+
+        // Release the buffer for the next report
+        self.write_reg(regs::STATUS, 0x00)?;
+        self.last_point = point.filter(|p| p.event != EventType::LiftUp);
+
+        Ok(point)
+    }
+
+    fn read_reg(&mut self, reg_addr: u16) -> Result<u8, I2C::Error> {
+        let mut buf = [0u8];
+        self.read_regs(reg_addr, &mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    fn read_regs(&mut self, reg_addr: u16, buf: &mut [u8]) -> Result<(), I2C::Error> {
+        self.i2c.write_read(self.addr, &reg_addr.to_be_bytes(), buf)
+    }
+
+    fn write_reg(&mut self, reg_addr: u16, value: u8) -> Result<(), I2C::Error> {
+        let [hi, lo] = reg_addr.to_be_bytes();
+        self.i2c.write(self.addr, &[hi, lo, value])?;
+
+        Ok(())
+    }
+}
+
+impl<I2C> TouchController for GT911<I2C>
+where
+    I2C: I2c,
+{
+    type Config = ();
+    type Error = Error<I2C::Error>;
+
+    fn init(&mut self, _config: ()) -> Result<(), Self::Error> {
+        GT911::init(self)
+    }
+
+    fn reset<P: OutputPin, D: DelayNs>(&mut self, rst: &mut P, delay: &mut D) -> Result<(), P::Error> {
+        GT911::reset(self, rst, delay)
+    }
+
+    fn read_report(&mut self) -> Result<Option<PointEvent>, Self::Error> {
+        Ok(self.get_point0()?)
+    }
+}
@@ -0,0 +1,202 @@
+//! Job submission protocol, spoken over a serial port with the host
+//!
+//! Every message is a [postcard](https://docs.rs/postcard) serialized [`Request`] (from the host) or [`Message`]
+//! (from the device), framed with COBS and terminated by a zero byte. The device answers each request with one
+//! message, and also sends [`Message::Progress`], [`Message::Completed`] and [`Message::Failed`] notifications as
+//! the jobs print.
+//!
+//! Shared by the firmware and the host crate, `host/src/jobs.rs` is the client.
+
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::printer_queue::{JobInfo, PrinterQueue, Status, TickEvent};
+
+/// Frames longer than this are garbage, e.g. from a wrong baud rate
+const MAX_FRAME: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Request {
+    Submit(JobInfo),
+    List,
+    Cancel { id: u32 },
+}
+
+/// A job as listed by [`Request::List`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JobEntry {
+    pub id: u32,
+    pub info: JobInfo,
+    pub status: Status,
+    pub progress: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Message {
+    /// Reply to [`Request::Submit`]
+    Submitted {
+        id: u32,
+    },
+    /// Reply to [`Request::List`], in queue order
+    Jobs(Vec<JobEntry>),
+    /// Reply to [`Request::Cancel`]
+    Cancelled {
+        id: u32,
+    },
+    /// Reply to [`Request::Cancel`] of a job that isn't queued
+    NotFound {
+        id: u32,
+    },
+    /// Reply to a request that couldn't be decoded
+    Invalid,
+    Progress {
+        id: u32,
+        progress: i32,
+    },
+    Completed {
+        id: u32,
+    },
+    Failed {
+        id: u32,
+    },
+}
+
+impl Message {
+    /// Notification of a tick of the queue
+    pub fn from_tick(event: &TickEvent) -> Option<Self> {
+        match event {
+            TickEvent::Idle => None,
+            TickEvent::Progress { id, progress } => Some(Message::Progress {
+                id: *id,
+                progress: *progress,
+            }),
+            TickEvent::Completed(job) => Some(Message::Completed { id: job.id }),
+            TickEvent::Failed { id } => Some(Message::Failed { id: *id }),
+        }
+    }
+}
+
+/// Notifications waiting to be sent, bounded while no host reads them
+///
+/// A progress updates the one of the same job that is still waiting, and the oldest progress is dropped when the queue
+/// is full. [`Message::Completed`] and [`Message::Failed`] are never dropped, there's at most one per job.
+pub struct NotificationQueue {
+    messages: VecDeque<Message>,
+    capacity: usize,
+}
+
+impl NotificationQueue {
+    pub const fn new(capacity: usize) -> Self {
+        NotificationQueue {
+            messages: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, message: Message) {
+        if let Message::Progress { id, progress } = message {
+            let waiting = self.messages.iter_mut().find_map(|message| match message {
+                Message::Progress { id: job, progress } if *job == id => Some(progress),
+                _ => None,
+            });
+            if let Some(waiting) = waiting {
+                *waiting = progress;
+                return;
+            }
+        }
+        self.messages.push_back(message);
+        if self.messages.len() > self.capacity {
+            if let Some(oldest) = self
+                .messages
+                .iter()
+                .position(|message| matches!(message, Message::Progress { .. }))
+            {
+                self.messages.remove(oldest);
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Message> {
+        self.messages.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+/// Execute `request` on `queue`, returns the reply
+pub fn handle(request: Request, queue: &mut PrinterQueue) -> Message {
+    match request {
+        Request::Submit(info) => Message::Submitted { id: queue.push(info) },
+        Request::List => Message::Jobs(
+            queue
+                .jobs()
+                .iter()
+                .map(|job| JobEntry {
+                    id: job.id,
+                    info: job.info.clone(),
+                    status: job.status,
+                    progress: job.progress,
+                })
+                .collect(),
+        ),
+        Request::Cancel { id } => match queue.position(id).and_then(|idx| queue.cancel(idx)) {
+            Some(_) => Message::Cancelled { id },
+            None => Message::NotFound { id },
+        },
+    }
+}
+
+/// Serialize and frame a message, including the terminating zero
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    // Only fails when out of memory
+    postcard::to_allocvec_cobs(message).unwrap()
+}
+
+/// Splits the received bytes into frames and decodes them
+#[derive(Default)]
+pub struct FrameReader {
+    buf: Vec<u8>,
+    /// Set while skipping the rest of an overlong frame
+    overflow: bool,
+}
+
+impl FrameReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Next complete frame, `Err` if it isn't a valid `T`
+    pub fn next_frame<T: DeserializeOwned>(&mut self) -> Option<Result<T, postcard::Error>> {
+        loop {
+            let Some(end) = self.buf.iter().position(|b| *b == 0) else {
+                if self.buf.len() > MAX_FRAME {
+                    self.buf.clear();
+                    self.overflow = true;
+                }
+                return None;
+            };
+            let mut frame: Vec<u8> = self.buf.drain(..=end).collect();
+            if core::mem::take(&mut self.overflow) {
+                return Some(Err(postcard::Error::DeserializeBadEncoding));
+            }
+            // Empty frames can be sent to resynchronize
+            if frame.len() > 1 {
+                return Some(postcard::from_bytes_cobs(&mut frame));
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Serves the [`job_protocol`](crate::job_protocol) on a UART, so that a host can submit and manage print jobs
+//!
+//! The host side is `host/src/bin/jobs.rs`.
+
+extern crate alloc;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use embassy_futures::join::join;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+
+use crate::job_protocol::{self, FrameReader, Message, NotificationQueue, Request};
+use crate::queue_model::PrinterQueueData;
+
+/// Notifications waiting to be sent, see [`NotificationQueue`] for what is dropped when no host reads them
+static NOTIFICATIONS: Mutex<CriticalSectionRawMutex, RefCell<NotificationQueue>> =
+    Mutex::new(RefCell::new(NotificationQueue::new(16)));
+/// Signaled when a notification is queued
+static NOTIFIED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Set by the requests running on the UI loop
+static REPLY: Signal<CriticalSectionRawMutex, Message> = Signal::new();
+
+/// The printer queue, moved into the requests posted to the UI loop
+///
+/// The queue and its Slint model belong to the UI loop, which runs on the same core as this task: the queue is only
+/// ever used by the closures the loop runs, never by this task.
+struct LoopQueue(Rc<PrinterQueueData>);
+
+// SAFETY: see above, there is a single core and the queue is only used on the UI loop
+unsafe impl Send for LoopQueue {}
+
+impl LoopQueue {
+    fn handle(&self, request: Request) -> Message {
+        self.0.handle(request)
+    }
+}
+
+/// Send a notification to the host
+pub fn notify(message: Message) {
+    NOTIFICATIONS.lock(|notifications| notifications.borrow_mut().push(message));
+    NOTIFIED.signal(());
+}
+
+/// Serve the requests received on `rx`, forever
+///
+/// Requests and notifications are waited for side by side, a notification never interrupts a read. The requests run
+/// on the UI loop, which wakes up for them, and their replies come back through [`REPLY`].
+pub async fn run<R, W>(mut rx: R, tx: W, printer_queue: Rc<PrinterQueueData>)
+where
+    R: embedded_io_async::Read,
+    W: embedded_io::Write,
+{
+    // Writes are blocking, the borrow is never held across an await
+    let tx = RefCell::new(tx);
+    let requests = async {
+        let mut reader = FrameReader::new();
+        let mut buf = [0; 64];
+        loop {
+            match rx.read(&mut buf).await {
+                Ok(n) => reader.push(&buf[..n]),
+                Err(e) => defmt::warn!("jobs: read failed: {}", defmt::Debug2Format(&e)),
+            }
+
+            while let Some(request) = reader.next_frame::<Request>() {
+                let reply = match request {
+                    Ok(request) => {
+                        defmt::debug!("jobs: {}", defmt::Debug2Format(&request));
+                        let queue = LoopQueue(printer_queue.clone());
+                        REPLY.reset();
+                        match slint::invoke_from_event_loop(move || REPLY.signal(queue.handle(request))) {
+                            Ok(()) => REPLY.wait().await,
+                            Err(e) => {
+                                defmt::warn!("jobs: no event loop: {}", defmt::Debug2Format(&e));
+                                Message::Invalid
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        defmt::warn!("jobs: invalid request: {}", defmt::Debug2Format(&e));
+                        Message::Invalid
+                    }
+                };
+                send(&mut *tx.borrow_mut(), &reply);
+            }
+        }
+    };
+    let notifications = async {
+        loop {
+            match NOTIFICATIONS.lock(|notifications| notifications.borrow_mut().pop()) {
+                Some(notification) => send(&mut *tx.borrow_mut(), &notification),
+                None => NOTIFIED.wait().await,
+            }
+        }
+    };
+    join(requests, notifications).await;
+}
+
+fn send<W: embedded_io::Write>(tx: &mut W, message: &Message) {
+    if let Err(e) = tx.write_all(&job_protocol::encode(message)) {
+        defmt::warn!("jobs: write failed: {}", defmt::Debug2Format(&e));
+    }
+}
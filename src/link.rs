@@ -0,0 +1,142 @@
+//! Packet framing of the serial link to the host tools
+//!
+//! All integers are little endian:
+//!
+//! ```text
+//! 0xA5 0x5A kind:u8 len:u16 payload:[u8; len] checksum:u8
+//! ```
+//!
+//! `checksum` is the wrapping sum of `kind`, the two `len` bytes and the payload. The packet kinds are defined by the
+//! users of the link, [`screenshot`](crate::screenshot) and [`remote`](crate::remote).
+//!
+//! Also built into the host crate, for the tools on the other end of the link.
+
+extern crate alloc;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+pub const SYNC: [u8; 2] = [0xA5, 0x5A];
+
+/// Sync, kind and length
+const HEADER_LEN: usize = 5;
+/// Packets from the host are small, anything longer is garbage
+const MAX_PAYLOAD: usize = 256;
+
+fn checksum(kind: u8, len: u16, parts: &[&[u8]]) -> u8 {
+    let sum = kind.wrapping_add(len as u8).wrapping_add((len >> 8) as u8);
+    parts
+        .iter()
+        .flat_map(|part| part.iter())
+        .fold(sum, |sum, b| sum.wrapping_add(*b))
+}
+
+/// Write one packet, its payload is the concatenation of `parts`
+pub fn write_packet<W: embedded_io::Write>(out: &mut W, kind: u8, parts: &[&[u8]]) -> Result<(), W::Error> {
+    let len = parts.iter().map(|part| part.len()).sum::<usize>() as u16;
+    out.write_all(&SYNC)?;
+    out.write_all(&[kind])?;
+    out.write_all(&len.to_le_bytes())?;
+    for part in parts {
+        out.write_all(part)?;
+    }
+    out.write_all(&[checksum(kind, len, parts)])
+}
+
+/// Splits the received bytes into packets, garbage and corrupted packets are dropped
+pub struct PacketReader {
+    buf: Vec<u8>,
+    max_payload: usize,
+    /// Packets dropped because of a bad checksum or length
+    pub errors: usize,
+}
+
+impl Default for PacketReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PacketReader {
+    /// Reader of the packets from the host
+    pub fn new() -> Self {
+        Self::with_max_payload(MAX_PAYLOAD)
+    }
+
+    /// Reader of packets of up to `max_payload` bytes, e.g. the screenshot lines on the host
+    pub fn with_max_payload(max_payload: usize) -> Self {
+        PacketReader {
+            buf: Vec::new(),
+            max_payload,
+            errors: 0,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Next complete packet as kind and payload
+    pub fn next_packet(&mut self) -> Option<(u8, Vec<u8>)> {
+        loop {
+            let start = self
+                .buf
+                .windows(2)
+                .position(|w| w == SYNC)
+                .unwrap_or(self.buf.len().saturating_sub(1));
+            self.buf.drain(..start);
+            if self.buf.len() < HEADER_LEN {
+                return None;
+            }
+            let kind = self.buf[2];
+            let len = u16::from_le_bytes([self.buf[3], self.buf[4]]);
+            let end = HEADER_LEN + len as usize;
+            if (len as usize) <= self.max_payload {
+                if self.buf.len() <= end {
+                    return None;
+                }
+                if checksum(kind, len, &[&self.buf[HEADER_LEN..end]]) == self.buf[end] {
+                    let payload = self.buf[HEADER_LEN..end].to_vec();
+                    self.buf.drain(..=end);
+                    return Some((kind, payload));
+                }
+            }
+            self.errors += 1;
+            #[cfg(target_os = "none")]
+            defmt::warn!("link: dropped a corrupted packet");
+            // Not a packet after all, look for the next sync
+            self.buf.drain(..1);
+        }
+    }
+}
+
+/// Writer shared by the users of the link
+///
+/// All of them run on the thread executor and write whole packets without awaiting, so packets don't interleave.
+pub struct SharedWriter<W>(Rc<RefCell<W>>);
+
+impl<W> SharedWriter<W> {
+    pub fn new(out: W) -> Self {
+        SharedWriter(Rc::new(RefCell::new(out)))
+    }
+}
+
+impl<W> Clone for SharedWriter<W> {
+    fn clone(&self) -> Self {
+        SharedWriter(self.0.clone())
+    }
+}
+
+impl<W: embedded_io::ErrorType> embedded_io::ErrorType for SharedWriter<W> {
+    type Error = W::Error;
+}
+
+impl<W: embedded_io::Write> embedded_io::Write for SharedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().flush()
+    }
+}
@@ -6,53 +6,209 @@ extern crate alloc;
 
 use alloc::boxed::Box;
 use alloc::rc::Rc;
+use alloc::vec;
 use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
 
+use buttons::{Button, Encoder, EncoderConfig};
 use defmt::info;
+use embassy_futures::select::select;
+use embassy_time::{Instant, Timer};
 use embedded_alloc::Heap;
 use embedded_graphics::draw_target::DrawTarget;
 use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
-use ft6236::FT6236;
-use hpm_hal::gpio::{Level, Output, Speed};
-use hpm_hal::mode::Blocking;
+use embedded_hal_async::digital::Wait as _;
+use embedded_storage::nor_flash::NorFlash;
+use hpm_hal::gpio::{Input, Level, Output, Pull, Speed};
+use hpm_hal::mode::{Async, Blocking};
 use hpm_hal::spi::{Config, Spi, Timings, MODE_0};
 use hpm_hal::time::Hertz;
+use job_protocol::Message;
+use link::SharedWriter;
+use print_engine::{PrintEngine, PrintEvent, PrintSettings};
+use printer_queue::{JobInfo, Status, Step, TickEvent};
+use profiler::Profiler;
+use queue_model::PrinterQueueData;
 use riscv::delay::McycleDelay;
 use rm67162::RM67162;
-use slint::{LogicalPosition, Model as _};
+use screenshot::ScreenshotWriter;
+use settings::{ColorMode, Layout, Quality, SettingsStore};
+use slint::platform::software_renderer::RenderingRotation;
+use slint::platform::{Key, WindowEvent};
+use slint::Model as _;
+use touch::{
+    CalibrationPoint, EventType, FilterConfig, GestureConfig, TouchBridge, TouchController, TouchReport,
+    TouchTransform, TOUCH_POLL_PERIOD, TOUCH_REPORTS,
+};
 use {defmt_rtt as _, hpm_hal as hal};
 
 use crate::slint_ui::*;
 
+mod buttons;
+mod console;
+#[cfg(feature = "cst816s")]
+mod cst816s;
+#[cfg(feature = "ft6236")]
 mod ft6236;
+#[cfg(feature = "gt911")]
+mod gt911;
+mod job_protocol;
+mod job_server;
+mod link;
+mod print_engine;
+mod printer_queue;
+mod profiler;
+mod queue_model;
+mod remote;
 mod rm67162;
+mod screenshot;
+mod settings;
 mod slint_ui;
-struct PrinterQueueData {
-    data: Rc<slint::VecModel<PrinterQueueItem>>,
-    print_progress_timer: slint::Timer,
+mod touch;
+
+#[cfg(not(any(feature = "ft6236", feature = "cst816s", feature = "gt911")))]
+compile_error!("Enable the feature of the touch controller: ft6236, cst816s or gt911");
+#[cfg(any(
+    all(feature = "ft6236", feature = "cst816s"),
+    all(feature = "ft6236", feature = "gt911"),
+    all(feature = "cst816s", feature = "gt911")
+))]
+compile_error!("Enable a single touch controller feature, e.g. with --no-default-features");
+
+/// Touch controller of the panel, selected by the cargo features
+#[cfg(feature = "ft6236")]
+type Touch = ft6236::FT6236<hal::i2c::I2c<'static, Blocking>>;
+#[cfg(feature = "cst816s")]
+type Touch = cst816s::CST816S<hal::i2c::I2c<'static, Blocking>>;
+#[cfg(feature = "gt911")]
+type Touch = gt911::GT911<hal::i2c::I2c<'static, Blocking>>;
+
+/// Period of the print engine simulation
+const PRINT_PERIOD: core::time::Duration = core::time::Duration::from_millis(100);
+
+/// Settings are saved once they stayed the same for this long, not at every tap
+const SETTINGS_SAVE_DELAY: core::time::Duration = core::time::Duration::from_secs(2);
+/// Size of the XPI0 flash
+const FLASH_SIZE: usize = 1024 * 1024;
+
+extern "C" {
+    // Offsets of the settings area in the flash, from `memory.x`
+    static __settings_start: u8;
+    static __settings_end: u8;
 }
 
-impl PrinterQueueData {
-    fn push_job(&self, title: slint::SharedString) {
-        self.data.push(PrinterQueueItem {
-            status: JobStatus::Waiting,
-            progress: 0,
-            title,
-            owner: env!("CARGO_PKG_AUTHORS").into(),
-            pages: 1,
-            size: "100kB".into(),
-            submission_date: "".into(),
-        })
+/// Settings of the UI, the night mode is in `DemoPalette`, the touch calibration is left out
+fn read_settings(main_window: &MainWindow) -> settings::Settings {
+    let settings = main_window.global::<Settings>();
+    let defaults = settings::Settings::default();
+    settings::Settings {
+        night_mode: main_window.global::<DemoPalette>().get_night_mode(),
+        layout: Layout::from_index(settings.get_layout()).unwrap_or(defaults.layout),
+        quality: Quality::from_index(settings.get_quality()).unwrap_or(defaults.quality),
+        color: ColorMode::from_index(settings.get_color()).unwrap_or(defaults.color),
+        eco_mode: settings.get_eco_mode(),
+        turbo: settings.get_turbo(),
+        touch_calibration: None,
     }
 }
 
+fn apply_settings(main_window: &MainWindow, values: &settings::Settings) {
+    main_window.global::<DemoPalette>().set_night_mode(values.night_mode);
+    let settings = main_window.global::<Settings>();
+    settings.set_layout(values.layout.index());
+    settings.set_quality(values.quality.index());
+    settings.set_color(values.color.index());
+    settings.set_eco_mode(values.eco_mode);
+    settings.set_turbo(values.turbo);
+}
+
+/// Saves the settings of the UI when they stopped changing for [`SETTINGS_SAVE_DELAY`]
+struct SettingsData<F> {
+    store: RefCell<SettingsStore<F>>,
+    /// Last settings loaded or saved
+    saved: Cell<settings::Settings>,
+    /// Not part of the UI, see [`SettingsData::calibrated`]
+    touch_calibration: Cell<Option<[[f32; 3]; 2]>>,
+    save_timer: slint::Timer,
+}
+
+impl<F: NorFlash + 'static> SettingsData<F> {
+    fn new(store: SettingsStore<F>, saved: settings::Settings) -> Self {
+        SettingsData {
+            store: RefCell::new(store),
+            saved: Cell::new(saved),
+            touch_calibration: Cell::new(saved.touch_calibration),
+            save_timer: Default::default(),
+        }
+    }
+
+    /// Restart the countdown to the save, at every change
+    fn changed(self: &Rc<Self>, main_window: slint::Weak<MainWindow>) {
+        let this = Rc::downgrade(self);
+        self.save_timer
+            .start(slint::TimerMode::SingleShot, SETTINGS_SAVE_DELAY, move || {
+                let (Some(this), Some(main_window)) = (this.upgrade(), main_window.upgrade()) else {
+                    return;
+                };
+                this.save(&settings::Settings {
+                    touch_calibration: this.touch_calibration.get(),
+                    ..read_settings(&main_window)
+                });
+            });
+    }
+
+    /// Keep the transform of a new touch calibration, saved along with the other settings
+    fn calibrated(self: &Rc<Self>, transform: TouchTransform, main_window: slint::Weak<MainWindow>) {
+        self.touch_calibration.set(Some([transform.m[0], transform.m[1]]));
+        self.changed(main_window);
+    }
+
+    /// Saved from the event loop, erasing a sector stalls the UI for a few tens of milliseconds
+    fn save(&self, settings: &settings::Settings) {
+        // Toggled back and forth
+        if *settings == self.saved.get() {
+            return;
+        }
+        // Not retried on failure, until the next change
+        self.saved.set(*settings);
+        match self.store.borrow_mut().save(settings) {
+            Ok(()) => info!("Settings saved"),
+            Err(e) => defmt::warn!("Settings save failed: {:?}", defmt::Debug2Format(&e)),
+        }
+    }
+}
+
+/// Rotation of the UI on the panel, done in software by the renderer
+const DISPLAY_ROTATION: RenderingRotation = RenderingRotation::NoRotation;
+
 #[global_allocator]
 static HEAP: Heap = Heap::empty();
 
+// The UARTs are read asynchronously, their tasks sleep until a byte arrives
+hal::bind_interrupts!(struct Irqs {
+    UART0 => hal::uart::InterruptHandler<hal::peripherals::UART0>;
+    UART2 => hal::uart::InterruptHandler<hal::peripherals::UART2>;
+    UART3 => hal::uart::InterruptHandler<hal::peripherals::UART3>;
+});
+
 // #[hal::entry]
 // fn main() -> ! {
+// Pins, keep in sync when adding peripherals:
+// - PA00/PA01: UART0 TX/RX, console and host tools
+// - PA02/PA03: UART2 TX/RX, text console
+// - PA04/PA05: UART3 TX/RX, job submission
+// - PA06/PA07/PA08, PB03: arrow buttons
+// - PA09: panel reset
+// - PA10/PA11/PA12: Backtab, Tab and Escape buttons
+// - PA26-PA31: SPI1, panel
+// - PB00/PB01: rotary encoder
+// - PB02: rotary encoder switch
+// - PB08/PB09: I2C2, touch controller
+// - PB10: touch interrupt
+// - PB12/PB13: panel IM and IOVCC
+// - PB14: touch reset
 #[embassy_executor::main]
-async fn main(_spawner: embassy_executor::Spawner) {
+async fn main(spawner: embassy_executor::Spawner) {
     let p = hal::init(Default::default());
 
     // Initialize the allocator BEFORE you use it
@@ -68,8 +224,6 @@ async fn main(_spawner: embassy_executor::Spawner) {
         slint::platform::software_renderer::RepaintBufferType::ReusedBuffer,
     );
 
-    slint::platform::set_platform(Box::new(MyPlatform { window: window.clone() })).unwrap();
-
     // Make sure the window covers our entire screen.
     // window.set_size(slint::PhysicalSize::new(600, 450));
     window.set_size(slint::PhysicalSize::new(536, 240));
@@ -117,10 +271,38 @@ async fn main(_spawner: embassy_executor::Spawner) {
     // Touch driver
     let i2c_config = hal::i2c::Config::default();
     let i2c = hal::i2c::I2c::new_blocking(p.I2C2, p.PB08, p.PB09, i2c_config);
-    let mut touch = FT6236::new_with_addr(i2c, 0x38);
+    let mut touch = Touch::new(i2c);
     let mut tp_rst = Output::new(p.PB14, Level::High, Speed::Fast);
-    touch.reset(&mut tp_rst, &mut embassy_time::Delay).unwrap();
-    touch.init(ft6236::Config::default()).unwrap();
+    TouchController::reset(&mut touch, &mut tp_rst, &mut embassy_time::Delay).unwrap();
+    TouchController::init(&mut touch, Default::default()).unwrap();
+    let touch_int = Input::new(p.PB10, Pull::Up);
+    spawner.must_spawn(touch_task(touch, touch_int));
+
+    let touch_transform = Rc::new(Cell::new(TouchTransform::default().rotate(DISPLAY_ROTATION, 536.0, 240.0)));
+    let mut touch_bridge =
+        TouchBridge::new(touch_transform.clone(), FilterConfig::default(), GestureConfig::default());
+    touch_bridge.on_gesture(|gesture| info!("Gesture: {:?}", defmt::Debug2Format(&gesture)));
+
+    let profiler = Profiler::new(hal::sysctl::clocks().cpu0.0);
+    let event_loop = Rc::new(EventLoop::new(window.clone(), display, touch_bridge, profiler));
+    event_loop.set_rotation(DISPLAY_ROTATION);
+    slint::platform::set_platform(Box::new(MyPlatform::new(window, event_loop.clone()))).unwrap();
+
+    // Console UART of the board, linked to the host tools: screenshots and remote control
+    let mut uart_config = hal::uart::Config::default();
+    uart_config.baudrate = 2_000_000;
+    let uart = hal::uart::Uart::new(p.UART0, p.PA01, p.PA00, Irqs, p.HDMA_CH1, p.HDMA_CH0, uart_config).unwrap();
+    let (uart_tx, uart_rx) = uart.split();
+    let uart_tx = SharedWriter::new(uart_tx);
+    event_loop.set_screenshot_sink(ScreenshotWriter::new(uart_tx.clone()));
+
+    // Reset and reinitialize the panel after a failed transfer, the next frame is repainted in full
+    event_loop.on_display_error(move |display| {
+        let mut delay = McycleDelay::new(hal::sysctl::clocks().cpu0.0);
+        if let Err(e) = display.reset(&mut rst, &mut delay).and_then(|_| display.init(&mut delay)) {
+            defmt::warn!("Display recovery failed: {:?}", e);
+        }
+    });
 
     info!("window set");
     let main_window = MainWindow::new().unwrap();
@@ -146,15 +328,58 @@ async fn main(_spawner: embassy_executor::Spawner) {
         .into(),
     );
 
-    let default_queue: Vec<PrinterQueueItem> = main_window
-        .global::<PrinterQueue>()
-        .get_printer_queue()
-        .iter()
-        .collect();
-    let printer_queue = Rc::new(PrinterQueueData {
-        data: Rc::new(slint::VecModel::from(default_queue.clone())),
-        print_progress_timer: Default::default(),
-    });
+    // Settings of the last run, applied before the first frame
+    let flash = hal::flash::Flash::<_, FLASH_SIZE>::new(p.XPI0, Default::default()).unwrap();
+    let (start, end) = unsafe {
+        (
+            core::ptr::addr_of!(__settings_start) as u32,
+            core::ptr::addr_of!(__settings_end) as u32,
+        )
+    };
+    let mut settings_store = SettingsStore::new(flash, start, end - start);
+    let saved_settings = match settings_store.load() {
+        Ok(settings) => settings.unwrap_or_default(),
+        Err(e) => {
+            defmt::warn!("Settings load failed: {:?}", defmt::Debug2Format(&e));
+            Default::default()
+        }
+    };
+    info!("Settings: {:?}", defmt::Debug2Format(&saved_settings));
+    apply_settings(&main_window, &saved_settings);
+    if let Some([x, y]) = saved_settings.touch_calibration {
+        touch_transform.set(TouchTransform {
+            m: [x, y, [0.0, 0.0, 1.0]],
+        });
+    }
+
+    // Owned by the callbacks, for as long as the window
+    let settings_data = Rc::new(SettingsData::new(settings_store, saved_settings));
+    let settings_data_copy = settings_data.clone();
+    let main_window_weak = main_window.as_weak();
+    main_window
+        .global::<Settings>()
+        .on_changed(move || settings_data_copy.changed(main_window_weak.clone()));
+
+    // Start with the demo jobs of the .slint file
+    let mut queue = printer_queue::PrinterQueue::new();
+    for item in main_window.global::<PrinterQueue>().get_printer_queue().iter() {
+        let status = match item.status {
+            JobStatus::Printing => Status::Printing,
+            JobStatus::Paused => Status::Paused,
+            JobStatus::Cancelled => Status::Cancelled,
+            JobStatus::Error => Status::Error,
+            _ => Status::Waiting,
+        };
+        let info = JobInfo {
+            title: item.title.as_str().into(),
+            owner: item.owner.as_str().into(),
+            pages: item.pages.max(1) as u32,
+            size: item.size.as_str().into(),
+            submission_date: item.submission_date.as_str().into(),
+        };
+        queue.insert(info, status, item.progress);
+    }
+    let printer_queue = Rc::new(PrinterQueueData::new(queue));
     main_window
         .global::<PrinterQueue>()
         .set_printer_queue(printer_queue.data.clone().into());
@@ -166,110 +391,273 @@ async fn main(_spawner: embassy_executor::Spawner) {
 
     let printer_queue_copy = printer_queue.clone();
     main_window.global::<PrinterQueue>().on_start_job(move |title| {
-        printer_queue_copy.push_job(title);
+        printer_queue_copy.push_job(title, 1, 1);
     });
 
+    let printer_queue_copy = printer_queue.clone();
+    main_window
+        .global::<PrinterQueue>()
+        .on_start_copy_job(move |title, copies| {
+            // The copier scans a single page
+            printer_queue_copy.push_job(title, 1, copies);
+        });
+
     let printer_queue_copy = printer_queue.clone();
     main_window.global::<PrinterQueue>().on_cancel_job(move |idx| {
-        printer_queue_copy.data.remove(idx as usize);
+        printer_queue_copy.cancel_job(idx as usize);
+    });
+
+    let printer_queue_copy = printer_queue.clone();
+    main_window.global::<PrinterQueue>().on_pause_job(move |idx| {
+        printer_queue_copy.toggle_pause(idx as usize);
     });
 
+    let mut print_engine = PrintEngine::new(
+        PrintSettings::default(),
+        main_window.get_ink_levels().iter().map(|ink| ink.level).collect(),
+    );
+    let mut last_progress = None;
+    let main_window_weak = main_window.as_weak();
     let printer_queue_weak = Rc::downgrade(&printer_queue);
-    printer_queue.print_progress_timer.start(
-        slint::TimerMode::Repeated,
-        core::time::Duration::from_millis(1),
-        move || {
-            if let Some(printer_queue) = printer_queue_weak.upgrade() {
-                if printer_queue.data.row_count() > 0 {
-                    let mut top_item = printer_queue.data.row_data(0).unwrap();
-                    top_item.progress += 1;
-                    top_item.status = JobStatus::Printing;
-                    if top_item.progress > 100 {
-                        printer_queue.data.remove(0);
-                        if printer_queue.data.row_count() == 0 {
-                            return;
-                        }
-                        top_item = printer_queue.data.row_data(0).unwrap();
+    printer_queue
+        .print_progress_timer
+        .start(slint::TimerMode::Repeated, PRINT_PERIOD, move || {
+            let (Some(printer_queue), Some(main_window)) = (printer_queue_weak.upgrade(), main_window_weak.upgrade())
+            else {
+                return;
+            };
+            let settings = main_window.global::<Settings>();
+            print_engine.set_settings(PrintSettings {
+                quality: Quality::from_index(settings.get_quality()).unwrap_or(Quality::Best),
+                turbo: settings.get_turbo(),
+            });
+            // Stopped for good once a cartridge is empty, there's no refilling in the demo
+            if print_engine.empty_ink().is_some() {
+                printer_queue.print_progress_timer.stop();
+                return;
+            }
+
+            let event = printer_queue.queue.borrow_mut().tick(|job, starting| {
+                if starting {
+                    print_engine.start_job(job.info.pages, job.progress);
+                }
+                let step = print_engine.run(PRINT_PERIOD);
+                match step.event {
+                    Some(PrintEvent::JobCompleted) => Step::Completed,
+                    Some(PrintEvent::OutOfInk(channel)) => {
+                        defmt::warn!("Ink {} is empty, job stopped: {}", channel, job.info.title.as_str());
+                        Step::Failed
                     }
-                    printer_queue.data.set_row_data(0, top_item);
-                } else {
-                    printer_queue.data.set_vec(default_queue.clone());
+                    None => Step::Progress(step.progress),
                 }
+            });
+            match &event {
+                TickEvent::Idle => {
+                    printer_queue.print_progress_timer.stop();
+                    return;
+                }
+                TickEvent::Completed(job) => info!("Job completed: {}", job.info.title.as_str()),
+                TickEvent::Progress { .. } | TickEvent::Failed { .. } => {}
             }
-        },
-    );
+            printer_queue.sync();
+            let ink_levels = main_window.get_ink_levels();
+            for (row, level) in print_engine.ink_levels().iter().enumerate() {
+                if let Some(mut ink) = ink_levels.row_data(row) {
+                    ink.level = *level;
+                    ink_levels.set_row_data(row, ink);
+                }
+            }
+            // Progress is only notified when it changed
+            if let TickEvent::Progress { id, progress } = event {
+                if last_progress.replace((id, progress)) == Some((id, progress)) {
+                    return;
+                }
+            }
+            if let Some(message) = Message::from_tick(&event) {
+                job_server::notify(message);
+            }
+        });
+
+    // Keys of the on-screen keyboard, typed into the focused text input
+    let main_window_weak = main_window.as_weak();
+    main_window
+        .global::<VirtualKeyboardHandler>()
+        .on_key_pressed(move |text| {
+            let main_window = main_window_weak.unwrap();
+            main_window
+                .window()
+                .dispatch_event(WindowEvent::KeyPressed { text: text.clone() });
+            main_window.window().dispatch_event(WindowEvent::KeyReleased { text });
+        });
 
-    let mut led = Output::new(p.PA10, Level::Low, Speed::Fast);
-    let mut line_buffer = [slint::platform::software_renderer::Rgb565Pixel::default(); 536];
-    // let mut line_buffer = [slint::platform::software_renderer::Rgb565Pixel::default(); 600];
-    let mut released_cycles = 0;
+    let calibration_points = Rc::new(RefCell::new(Vec::<CalibrationPoint>::new()));
 
-    info!("Starting event loop");
-    loop {
-        // Let Slint run the timer hooks and update animations.
-        slint::platform::update_timers_and_animations();
-
-        // Check the touch screen or input device using your driver.
-        if let Ok(Some(mut point)) = touch.get_point0() {
-            // Create event
-            released_cycles = 0;
-            let x = point.x;
-            point.x = point.y;
-            point.y = 240 - x;
-            info!("Point: {:?}", point);
-            let e = match point.event {
-                ft6236::EventType::PressDown => slint::platform::WindowEvent::PointerPressed {
-                    position: LogicalPosition {
-                        x: point.x as f32,
-                        y: point.y as f32,
-                    },
-                    button: slint::platform::PointerEventButton::Left,
-                },
-                ft6236::EventType::LiftUp => slint::platform::WindowEvent::PointerReleased {
-                    position: LogicalPosition {
-                        x: point.x as f32,
-                        y: point.y as f32,
-                    },
-                    button: slint::platform::PointerEventButton::Left,
-                },
-                ft6236::EventType::Contact => slint::platform::WindowEvent::PointerMoved {
-                    position: LogicalPosition {
-                        x: point.x as f32,
-                        y: point.y as f32,
-                    },
-                },
+    let calibration_points_copy = calibration_points.clone();
+    let main_window_weak = main_window.as_weak();
+    main_window.global::<Calibration>().on_start(move || {
+        calibration_points_copy.borrow_mut().clear();
+        let main_window = main_window_weak.unwrap();
+        main_window.global::<Calibration>().set_step(0);
+        main_window.global::<Calibration>().set_active(true);
+    });
+
+    let main_window_weak = main_window.as_weak();
+    main_window
+        .global::<Calibration>()
+        .on_sample(move |target_x, target_y, x, y| {
+            let main_window = main_window_weak.unwrap();
+            let calibration = main_window.global::<Calibration>();
+            // Samples are in screen space, undo the current transform to get the raw touch coordinates
+            let Some(inverse) = touch_transform.get().inverse() else {
+                return;
             };
-            window.dispatch_event(e);
-        } else {
-            released_cycles += 1;
-            if released_cycles > 100 {
-                window.dispatch_event(slint::platform::WindowEvent::PointerReleased {
-                    position: LogicalPosition { x: 0_f32, y: 0_f32 },
-                    button: slint::platform::PointerEventButton::Left,
-                });
+            let mut points = calibration_points.borrow_mut();
+            points.push(CalibrationPoint {
+                screen: (target_x, target_y),
+                raw: inverse.apply(x, y),
+            });
 
-                window.dispatch_event(slint::platform::WindowEvent::PointerExited);
+            if points.len() < calibration.get_targets().row_count() {
+                calibration.set_step(points.len() as i32);
+                return;
             }
-        };
-
-        // Draw the scene if something needs to be drawn.
-        window.draw_if_needed(|renderer| {
-            // Use single line buffer
-            renderer.render_by_line(DisplayWrapper {
-                display: &mut display,
-                line_buffer: &mut line_buffer,
-            });
+            match TouchTransform::from_calibration(&points) {
+                Some(transform) => {
+                    info!("Touch calibrated: {:?}", defmt::Debug2Format(&transform));
+                    touch_transform.set(transform);
+                    settings_data.calibrated(transform, main_window.as_weak());
+                }
+                None => defmt::warn!("Touch calibration failed, keeping the previous transform"),
+            }
+            calibration.set_active(false);
         });
 
-        // Try to put the MCU to sleep
-        if !window.has_active_animations() {
-            if let Some(duration) = slint::platform::duration_until_next_timer_update() {
-                // embassy_time::Timer::after_millis(duration.as_millis() as u64).await;
-                continue;
+    let main_window_weak = main_window.as_weak();
+    event_loop.on_stats(move |stats| {
+        let main_window = main_window_weak.unwrap();
+        let perf = main_window.global::<Perf>();
+        // Logged along with the overlay, not to flood the log every second
+        if perf.get_overlay_visible() {
+            info!("{:?}", stats);
+        }
+        perf.set_fps(stats.fps() as i32);
+        perf.set_render_ms(stats.render_us.avg() as f32 / 1000.0);
+        perf.set_flush_ms(stats.flush_us.avg() as f32 / 1000.0);
+        perf.set_lines(stats.lines.avg() as i32);
+        perf.set_input_latency_ms(stats.input_latency_us.avg() as f32 / 1000.0);
+        perf.set_loop_hz(stats.loop_hz() as i32);
+    });
+
+    spawner.must_spawn(remote_task(uart_rx, uart_tx, main_window.as_weak()));
+
+    // Text console for typing into the UI from a terminal, on the pins of the expansion header
+    let mut console_config = hal::uart::Config::default();
+    console_config.baudrate = 115_200;
+    let console = hal::uart::Uart::new(p.UART2, p.PA03, p.PA02, Irqs, p.HDMA_CH3, p.HDMA_CH2, console_config).unwrap();
+    let (_, console_rx) = console.split();
+    spawner.must_spawn(console_task(console_rx, main_window.as_weak()));
+
+    // Job submission from a host, with the `jobs` tool of the host crate
+    let mut jobs_config = hal::uart::Config::default();
+    jobs_config.baudrate = 115_200;
+    let jobs_uart = hal::uart::Uart::new(p.UART3, p.PA05, p.PA04, Irqs, p.HDMA_CH5, p.HDMA_CH4, jobs_config).unwrap();
+    let (jobs_tx, jobs_rx) = jobs_uart.split();
+    spawner.must_spawn(job_server_task(jobs_rx, jobs_tx, printer_queue.clone()));
+
+    // Keys for operating the UI without the touch panel, the encoder moves the focus and its switch activates. The
+    // arrows step the spin boxes and combo boxes, Escape closes the job dialog.
+    let buttons = vec![
+        Button::new(Input::new(p.PA10, Pull::Up), Key::Backtab),
+        Button::new(Input::new(p.PA11, Pull::Up), Key::Tab),
+        Button::new(Input::new(p.PA12, Pull::Up), Key::Escape),
+        Button::new(Input::new(p.PB02, Pull::Up), Key::Return),
+        Button::new(Input::new(p.PA06, Pull::Up), Key::UpArrow),
+        Button::new(Input::new(p.PA07, Pull::Up), Key::DownArrow),
+        Button::new(Input::new(p.PA08, Pull::Up), Key::LeftArrow),
+        Button::new(Input::new(p.PB03, Pull::Up), Key::RightArrow),
+    ];
+    let encoder = Encoder::new(
+        Input::new(p.PB00, Pull::Up),
+        Input::new(p.PB01, Pull::Up),
+        EncoderConfig::default(),
+    );
+    spawner.must_spawn(buttons_task(buttons, Some(encoder), main_window.as_weak()));
+    spawner.must_spawn(ui_task(main_window, event_loop));
+
+    // The tasks outlive `main`, keep the panel powered and out of reset
+    core::mem::forget((im, iovcc, tp_rst));
+}
+
+/// Owns the Slint window, renders it and dispatches the touch reports
+#[embassy_executor::task]
+async fn ui_task(main_window: MainWindow, event_loop: Rc<EventLoop<RM67162<'static>>>) {
+    info!("Starting event loop");
+    main_window.show().unwrap();
+    event_loop.run().await;
+    main_window.hide().unwrap();
+}
+
+/// Runs the commands of the host test tools
+#[embassy_executor::task]
+async fn remote_task(
+    rx: hal::uart::UartRx<'static, Async>,
+    tx: SharedWriter<hal::uart::UartTx<'static, Async>>,
+    main_window: slint::Weak<MainWindow>,
+) {
+    remote::run(rx, tx, main_window).await;
+}
+
+/// Types the characters received on the console UART
+#[embassy_executor::task]
+async fn console_task(rx: hal::uart::UartRx<'static, Async>, main_window: slint::Weak<MainWindow>) {
+    console::run(rx, main_window).await;
+}
+
+/// Serves the job submission protocol
+#[embassy_executor::task]
+async fn job_server_task(
+    rx: hal::uart::UartRx<'static, Async>,
+    tx: hal::uart::UartTx<'static, Async>,
+    printer_queue: Rc<PrinterQueueData>,
+) {
+    job_server::run(rx, tx, printer_queue).await;
+}
+
+/// Turns the GPIO buttons and the rotary encoder into key events
+#[embassy_executor::task]
+async fn buttons_task(
+    buttons: Vec<Button<Input<'static>>>,
+    encoder: Option<Encoder<Input<'static>>>,
+    main_window: slint::Weak<MainWindow>,
+) {
+    buttons::run(buttons, encoder, main_window).await;
+}
+
+/// Reads the touch controller and posts its reports to the UI task
+#[embassy_executor::task]
+async fn touch_task(mut touch: Touch, mut touch_int: Input<'static>) {
+    let mut touching = false;
+    loop {
+        if touching {
+            // Not every interrupt mode pulses once the finger is lifted, poll until the panel is released
+            select(Timer::after(TOUCH_POLL_PERIOD), touch_int.wait_for_falling_edge()).await;
+        } else {
+            touch_int.wait_for_falling_edge().await.ok();
+        }
+
+        match touch.read_report() {
+            Ok(Some(point)) => {
+                touching = point.event != EventType::LiftUp;
+                TOUCH_REPORTS
+                    .send(TouchReport {
+                        point,
+                        read_at: Instant::now(),
+                    })
+                    .await;
             }
+            Ok(None) => touching = false,
+            Err(e) => defmt::warn!("Touch read error: {:?}", e),
         }
-        led.toggle();
-        // embassy_time::Timer::after_millis(1).await;
     }
 }
 
@@ -0,0 +1,143 @@
+//! Simulated print engine
+//!
+//! Prints the pages of one job at a time, at a speed set by the print quality and the TURBO setting, and uses ink for
+//! every page. Knows nothing about the queue, the caller picks the job and applies the reported progress.
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::settings::Quality;
+
+/// Ink used by a page of medium quality, as a fraction of a full cartridge
+const INK_PER_PAGE: f32 = 0.002;
+/// Speed factor of the TURBO setting
+const TURBO_SPEEDUP: f32 = 1.5;
+
+fn pages_per_minute(quality: Quality) -> f32 {
+    match quality {
+        Quality::Best => 8.0,
+        Quality::Medium => 15.0,
+        Quality::Draft => 24.0,
+    }
+}
+
+fn ink_factor(quality: Quality) -> f32 {
+    match quality {
+        Quality::Best => 1.5,
+        Quality::Medium => 1.0,
+        Quality::Draft => 0.5,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PrintSettings {
+    pub quality: Quality,
+    pub turbo: bool,
+}
+
+impl Default for PrintSettings {
+    fn default() -> Self {
+        PrintSettings {
+            quality: Quality::Best,
+            turbo: true,
+        }
+    }
+}
+
+impl PrintSettings {
+    pub fn pages_per_minute(&self) -> f32 {
+        let speedup = if self.turbo { TURBO_SPEEDUP } else { 1.0 };
+        pages_per_minute(self.quality) * speedup
+    }
+}
+
+/// What happened while printing
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum PrintEvent {
+    /// All the pages of the job are printed
+    JobCompleted,
+    /// The ink of this channel ran out, the job can't go on
+    OutOfInk(usize),
+}
+
+/// Result of [`PrintEngine::run`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct PrintStep {
+    /// Progress of the job in percent
+    pub progress: i32,
+    pub event: Option<PrintEvent>,
+}
+
+pub struct PrintEngine {
+    settings: PrintSettings,
+    /// Level of every ink channel, from 0 to 1
+    ink_levels: Vec<f32>,
+    /// Pages of the current job printed so far, the last one partially
+    printed: f32,
+    pages: u32,
+}
+
+impl PrintEngine {
+    pub fn new(settings: PrintSettings, ink_levels: Vec<f32>) -> Self {
+        PrintEngine {
+            settings,
+            ink_levels,
+            printed: 0.0,
+            pages: 0,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: PrintSettings) {
+        self.settings = settings;
+    }
+
+    pub fn ink_levels(&self) -> &[f32] {
+        &self.ink_levels
+    }
+
+    /// Channel of the first empty ink cartridge
+    pub fn empty_ink(&self) -> Option<usize> {
+        self.ink_levels.iter().position(|level| *level <= 0.0)
+    }
+
+    /// Start or resume a job of `pages` pages, `progress` is the percentage already printed
+    pub fn start_job(&mut self, pages: u32, progress: i32) {
+        self.pages = pages.max(1);
+        self.printed = self.pages as f32 * progress.clamp(0, 100) as f32 / 100.0;
+    }
+
+    /// Print the current job for `elapsed`
+    pub fn run(&mut self, elapsed: Duration) -> PrintStep {
+        if let Some(channel) = self.empty_ink() {
+            return PrintStep {
+                progress: self.progress(),
+                event: Some(PrintEvent::OutOfInk(channel)),
+            };
+        }
+
+        let pages =
+            (self.settings.pages_per_minute() * elapsed.as_secs_f32() / 60.0).min(self.pages as f32 - self.printed);
+        let ink = pages * INK_PER_PAGE * ink_factor(self.settings.quality);
+        for level in self.ink_levels.iter_mut() {
+            *level = (*level - ink).max(0.0);
+        }
+        self.printed += pages;
+
+        let event = if self.printed >= self.pages as f32 {
+            Some(PrintEvent::JobCompleted)
+        } else {
+            self.empty_ink().map(PrintEvent::OutOfInk)
+        };
+        PrintStep {
+            progress: self.progress(),
+            event,
+        }
+    }
+
+    fn progress(&self) -> i32 {
+        (self.printed * 100.0 / self.pages as f32) as i32
+    }
+}
@@ -0,0 +1,188 @@
+//! Print queue, independent of the UI and of the hardware
+//!
+//! The jobs are printed in order, one at a time. The queue decides which job runs, the caller does the printing in
+//! [`PrinterQueue::tick`] and the UI mirrors [`PrinterQueue::jobs`].
+//!
+//! Also built into the host crate, which has the tests, see `host/tests/printer_queue.rs`.
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    Waiting,
+    Printing,
+    /// Skipped until resumed
+    Paused,
+    Cancelled,
+    /// Printing failed, kept in the queue until deleted
+    Error,
+}
+
+/// Description of a new job
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub title: String,
+    pub owner: String,
+    pub pages: u32,
+    pub size: String,
+    pub submission_date: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Job {
+    /// Unique for the lifetime of the queue
+    pub id: u32,
+    pub info: JobInfo,
+    pub status: Status,
+    /// In percent
+    pub progress: i32,
+}
+
+/// Outcome of printing for one tick
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Still printing, at this progress in percent
+    Progress(i32),
+    Completed,
+    Failed,
+}
+
+/// What a tick did
+#[derive(Clone, Debug, PartialEq)]
+pub enum TickEvent {
+    /// No job to print
+    Idle,
+    Progress {
+        id: u32,
+        progress: i32,
+    },
+    /// The job is done and removed from the queue
+    Completed(Job),
+    /// The job is kept with the [`Status::Error`] status
+    Failed {
+        id: u32,
+    },
+}
+
+#[derive(Default)]
+pub struct PrinterQueue {
+    jobs: Vec<Job>,
+    next_id: u32,
+}
+
+impl PrinterQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    // Only used by the host tests
+    #[cfg(not(target_os = "none"))]
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    pub fn position(&self, id: u32) -> Option<usize> {
+        self.jobs.iter().position(|job| job.id == id)
+    }
+
+    /// Add a waiting job at the end of the queue, returns its id
+    pub fn push(&mut self, info: JobInfo) -> u32 {
+        self.insert(info, Status::Waiting, 0)
+    }
+
+    /// Add a job in any state, e.g. demo data
+    pub fn insert(&mut self, info: JobInfo, status: Status, progress: i32) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.jobs.push(Job {
+            id,
+            info,
+            status,
+            progress: progress.clamp(0, 100),
+        });
+        id
+    }
+
+    /// Remove a job, whatever its state. The next tick starts the next job if it was printing.
+    pub fn cancel(&mut self, idx: usize) -> Option<Job> {
+        if idx >= self.jobs.len() {
+            return None;
+        }
+        let mut job = self.jobs.remove(idx);
+        job.status = Status::Cancelled;
+        Some(job)
+    }
+
+    /// Pause a waiting or printing job, or resume a paused one. Returns false if the job can't be paused.
+    pub fn toggle_pause(&mut self, idx: usize) -> bool {
+        let Some(job) = self.jobs.get_mut(idx) else {
+            return false;
+        };
+        job.status = match job.status {
+            Status::Waiting | Status::Printing => Status::Paused,
+            Status::Paused => Status::Waiting,
+            Status::Cancelled | Status::Error => return false,
+        };
+        true
+    }
+
+    /// Move the job at `from` to `to`, shifting the jobs in between
+    // Only used by the host tests, the UI has no reordering
+    #[cfg(not(target_os = "none"))]
+    pub fn move_job(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.jobs.len() || to >= self.jobs.len() {
+            return false;
+        }
+        let job = self.jobs.remove(from);
+        self.jobs.insert(to, job);
+        true
+    }
+
+    /// The job being printed, else the first waiting one
+    pub fn next_runnable(&self) -> Option<usize> {
+        let position = |status| self.jobs.iter().position(|job| job.status == status);
+        position(Status::Printing).or_else(|| position(Status::Waiting))
+    }
+
+    /// Print the next runnable job with `print`
+    ///
+    /// `print` gets the job and whether it starts, or resumes, printing with this tick.
+    pub fn tick(&mut self, print: impl FnOnce(&Job, bool) -> Step) -> TickEvent {
+        let Some(idx) = self.next_runnable() else {
+            return TickEvent::Idle;
+        };
+        let job = &mut self.jobs[idx];
+        let starting = job.status != Status::Printing;
+        job.status = Status::Printing;
+        match print(job, starting) {
+            Step::Progress(progress) => {
+                job.progress = progress.clamp(0, 100);
+                TickEvent::Progress {
+                    id: job.id,
+                    progress: job.progress,
+                }
+            }
+            Step::Completed => {
+                let mut job = self.jobs.remove(idx);
+                job.progress = 100;
+                TickEvent::Completed(job)
+            }
+            Step::Failed => {
+                job.status = Status::Error;
+                TickEvent::Failed { id: job.id }
+            }
+        }
+    }
+}
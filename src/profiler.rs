@@ -0,0 +1,129 @@
+//! Frame timing instrumentation based on the `mcycle` counter
+//!
+
+use embassy_time::Duration;
+
+/// Stats are aggregated and reported once per period
+const REPORT_PERIOD_US: u64 = 1_000_000;
+
+/// Current value of the `mcycle` counter
+pub fn cycles() -> u64 {
+    riscv::register::mcycle::read64()
+}
+
+#[derive(Copy, Clone, Default, Debug, defmt::Format)]
+pub struct Stat {
+    pub min: u32,
+    pub max: u32,
+    sum: u64,
+    count: u32,
+}
+
+impl Stat {
+    fn add(&mut self, value: u32) {
+        if self.count == 0 || value < self.min {
+            self.min = value;
+        }
+        self.max = self.max.max(value);
+        self.sum += value as u64;
+        self.count += 1;
+    }
+
+    pub fn avg(&self) -> u32 {
+        if self.count == 0 {
+            0
+        } else {
+            (self.sum / self.count as u64) as u32
+        }
+    }
+}
+
+/// Rolling stats over the last report period, times are in microseconds
+#[derive(Copy, Clone, Default, Debug, defmt::Format)]
+pub struct FrameStats {
+    pub frames: u32,
+    /// Time spent rendering the frames, without pushing the lines to the display
+    pub render_us: Stat,
+    /// Time spent pushing the lines to the display
+    pub flush_us: Stat,
+    /// Lines rendered per frame
+    pub lines: Stat,
+    /// Time between reading a touch report and dispatching it to the window
+    pub input_latency_us: Stat,
+    pub loop_iterations: u32,
+    pub period_us: u32,
+}
+
+impl FrameStats {
+    pub fn fps(&self) -> u32 {
+        (self.frames as u64 * 1_000_000 / self.period_us.max(1) as u64) as u32
+    }
+
+    pub fn loop_hz(&self) -> u32 {
+        (self.loop_iterations as u64 * 1_000_000 / self.period_us.max(1) as u64) as u32
+    }
+}
+
+pub struct Profiler {
+    cycles_per_us: u64,
+    period_start: u64,
+    stats: FrameStats,
+    frame_flush: u64,
+    frame_lines: u32,
+}
+
+impl Profiler {
+    pub fn new(cpu_hz: u32) -> Self {
+        Profiler {
+            cycles_per_us: (cpu_hz as u64 / 1_000_000).max(1),
+            period_start: cycles(),
+            stats: FrameStats::default(),
+            frame_flush: 0,
+            frame_lines: 0,
+        }
+    }
+
+    fn to_us(&self, cycles: u64) -> u32 {
+        (cycles / self.cycles_per_us) as u32
+    }
+
+    pub fn loop_iteration(&mut self) {
+        self.stats.loop_iterations += 1;
+    }
+
+    /// A line was pushed to the display in `cycles`
+    pub fn line_flushed(&mut self, cycles: u64) {
+        self.frame_flush += cycles;
+        self.frame_lines += 1;
+    }
+
+    /// A frame was rendered and flushed in `cycles`
+    pub fn frame_done(&mut self, cycles: u64) {
+        let flush = self.to_us(self.frame_flush);
+        self.stats.frames += 1;
+        self.stats.render_us.add(self.to_us(cycles.saturating_sub(self.frame_flush)));
+        self.stats.flush_us.add(flush);
+        self.stats.lines.add(self.frame_lines);
+        self.frame_flush = 0;
+        self.frame_lines = 0;
+    }
+
+    pub fn input_dispatched(&mut self, latency: Duration) {
+        self.stats.input_latency_us.add(latency.as_micros() as u32);
+    }
+
+    /// Stats of the last period, once the period is over
+    pub fn poll_report(&mut self) -> Option<FrameStats> {
+        let now = cycles();
+        let elapsed = self.to_us(now - self.period_start);
+        if (elapsed as u64) < REPORT_PERIOD_US {
+            return None;
+        }
+        self.period_start = now;
+        let stats = FrameStats {
+            period_us: elapsed,
+            ..core::mem::take(&mut self.stats)
+        };
+        Some(stats)
+    }
+}
@@ -0,0 +1,126 @@
+//! Slint model of the print queue, mirrored from the [`PrinterQueue`]
+//!
+//! Also built into the host crate, which has the tests, see `host/tests/queue_model.rs`.
+
+extern crate alloc;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use slint::Model as _;
+
+use crate::printer_queue::{Job, JobInfo, PrinterQueue, Status};
+use crate::{job_protocol, JobStatus, PrinterQueueItem};
+
+/// Mirrors the [`PrinterQueue`] into the model of the Slint `PrinterQueue`
+///
+/// Belongs to the UI loop, like the model: the other tasks post their changes to the loop.
+pub struct PrinterQueueData {
+    pub queue: RefCell<PrinterQueue>,
+    pub data: Rc<slint::VecModel<PrinterQueueItem>>,
+    /// Job of every row of `data`
+    ids: RefCell<Vec<u32>>,
+    /// Runs the print engine, its callback is set by the caller
+    pub print_progress_timer: slint::Timer,
+}
+
+impl PrinterQueueData {
+    pub fn new(queue: PrinterQueue) -> Self {
+        let data = PrinterQueueData {
+            queue: RefCell::new(queue),
+            data: Default::default(),
+            ids: Default::default(),
+            print_progress_timer: Default::default(),
+        };
+        data.sync();
+        data
+    }
+
+    /// Queue a job of `copies` copies of a document of `pages` pages, printed as one job
+    pub fn push_job(&self, title: slint::SharedString, pages: u32, copies: i32) {
+        self.queue.borrow_mut().push(JobInfo {
+            title: title.as_str().into(),
+            owner: env!("CARGO_PKG_AUTHORS").into(),
+            pages: pages * copies.max(1) as u32,
+            size: "100kB".into(),
+            submission_date: "".into(),
+        });
+        self.sync();
+    }
+
+    pub fn cancel_job(&self, idx: usize) {
+        self.queue.borrow_mut().cancel(idx);
+        self.sync();
+    }
+
+    pub fn toggle_pause(&self, idx: usize) {
+        self.queue.borrow_mut().toggle_pause(idx);
+        self.sync();
+    }
+
+    /// Execute a request of the host, see [`job_protocol`]
+    pub fn handle(&self, request: job_protocol::Request) -> job_protocol::Message {
+        let reply = job_protocol::handle(request, &mut self.queue.borrow_mut());
+        self.sync();
+        reply
+    }
+
+    /// Update the model rows that changed, and run the print timer only while there is something to print
+    ///
+    /// Rows are inserted and removed rather than overwritten, so that the delegates keep their state, e.g. expanded.
+    pub fn sync(&self) {
+        let queue = self.queue.borrow();
+        let runnable = queue.next_runnable().is_some();
+        if runnable && !self.print_progress_timer.running() {
+            // No-op until the callback is set with `start`
+            self.print_progress_timer.restart();
+        } else if !runnable {
+            self.print_progress_timer.stop();
+        }
+
+        let mut ids = self.ids.borrow_mut();
+        for (row, job) in queue.jobs().iter().enumerate() {
+            // Drop the rows of the removed jobs
+            while row < ids.len() && queue.position(ids[row]).is_none() {
+                ids.remove(row);
+                self.data.remove(row);
+            }
+            let item = queue_item(job);
+            if ids.get(row) == Some(&job.id) {
+                if self.data.row_data(row).as_ref() != Some(&item) {
+                    self.data.set_row_data(row, item);
+                }
+                continue;
+            }
+            // Moved up from further down
+            if let Some(old_row) = ids.iter().skip(row).position(|id| *id == job.id) {
+                ids.remove(row + old_row);
+                self.data.remove(row + old_row);
+            }
+            ids.insert(row, job.id);
+            self.data.insert(row, item);
+        }
+        for row in (queue.len()..ids.len()).rev() {
+            ids.remove(row);
+            self.data.remove(row);
+        }
+    }
+}
+
+fn queue_item(job: &Job) -> PrinterQueueItem {
+    PrinterQueueItem {
+        status: match job.status {
+            Status::Waiting => JobStatus::Waiting,
+            Status::Printing => JobStatus::Printing,
+            Status::Paused => JobStatus::Paused,
+            Status::Cancelled => JobStatus::Cancelled,
+            Status::Error => JobStatus::Error,
+        },
+        progress: job.progress,
+        title: job.info.title.as_str().into(),
+        owner: job.info.owner.as_str().into(),
+        pages: job.info.pages as i32,
+        size: job.info.size.as_str().into(),
+        submission_date: job.info.submission_date.as_str().into(),
+    }
+}
@@ -0,0 +1,190 @@
+//! Remote control of the UI over the serial link, for automated tests on the device
+//!
+//! The host sends one command at a time as a [`link`](crate::link) packet and waits for its reply. Commands:
+//!
+//! - [`PRESS`], [`MOVE`], [`RELEASE`]: `x:f32 y:f32` in logical pixels
+//! - [`KEY`]: the UTF-8 text of the key, pressed and released
+//! - [`QUERY`]: `property:u8`, see [`Property`]
+//! - [`WAIT_IDLE`]: replies once the animations are done
+//! - [`SCREENSHOT`]: replies right away, the frame follows as [`screenshot`](crate::screenshot) packets
+//!
+//! Replies are [`OK`], [`VALUE`] with an `i32` for queries, or [`ERROR`] with a `code:u8`.
+//!
+//! The host side is `host/src/remote.rs`.
+
+extern crate alloc;
+use alloc::string::String;
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use slint::platform::{PointerEventButton, WindowEvent};
+use slint::{ComponentHandle, LogicalPosition, Model as _};
+
+use crate::link::{self, PacketReader};
+use crate::screenshot;
+use crate::slint_ui::{Calibration, DemoPalette, MainWindow, PrinterQueue};
+
+mod protocol;
+
+pub use protocol::*;
+
+/// Time between the checks of [`WAIT_IDLE`], at least one frame is drawn in between
+const IDLE_POLL_PERIOD: core::time::Duration = core::time::Duration::from_millis(20);
+
+impl Property {
+    fn read(self, main_window: &MainWindow) -> i32 {
+        match self {
+            Property::ActivePage => main_window.get_active_page(),
+            Property::QueueLength => main_window.global::<PrinterQueue>().get_printer_queue().row_count() as i32,
+            Property::NightMode => main_window.global::<DemoPalette>().get_night_mode() as i32,
+            Property::CalibrationActive => main_window.global::<Calibration>().get_active() as i32,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, defmt::Format)]
+pub enum ErrorCode {
+    UnknownCommand = 1,
+    Malformed = 2,
+    UnknownProperty = 3,
+    /// The main window is gone
+    NoWindow = 4,
+}
+
+#[derive(Debug)]
+enum Command {
+    Press(LogicalPosition),
+    Move(LogicalPosition),
+    Release(LogicalPosition),
+    Key(String),
+    Query(Property),
+    WaitIdle,
+    Screenshot,
+}
+
+impl Command {
+    fn parse(kind: u8, payload: &[u8]) -> Result<Self, ErrorCode> {
+        let position = || match payload {
+            [x0, x1, x2, x3, y0, y1, y2, y3] => Ok(LogicalPosition::new(
+                f32::from_le_bytes([*x0, *x1, *x2, *x3]),
+                f32::from_le_bytes([*y0, *y1, *y2, *y3]),
+            )),
+            _ => Err(ErrorCode::Malformed),
+        };
+        Ok(match kind {
+            PRESS => Command::Press(position()?),
+            MOVE => Command::Move(position()?),
+            RELEASE => Command::Release(position()?),
+            KEY => Command::Key(String::from(
+                core::str::from_utf8(payload).map_err(|_| ErrorCode::Malformed)?,
+            )),
+            QUERY => match payload {
+                [property] => Command::Query(Property::from_u8(*property).ok_or(ErrorCode::UnknownProperty)?),
+                _ => return Err(ErrorCode::Malformed),
+            },
+            WAIT_IDLE => Command::WaitIdle,
+            SCREENSHOT => Command::Screenshot,
+            _ => return Err(ErrorCode::UnknownCommand),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, defmt::Format)]
+enum Reply {
+    Ok,
+    Value(i32),
+    Error(ErrorCode),
+}
+
+/// Set by the commands running on the UI loop
+static REPLY: Signal<CriticalSectionRawMutex, Reply> = Signal::new();
+
+/// Receive commands from `rx` and write the replies to `tx`, forever
+pub async fn run<R, W>(mut rx: R, mut tx: W, main_window: slint::Weak<MainWindow>)
+where
+    R: embedded_io_async::Read,
+    W: embedded_io::Write,
+{
+    let mut reader = PacketReader::new();
+    let mut buf = [0; 64];
+    loop {
+        match rx.read(&mut buf).await {
+            Ok(n) => reader.push(&buf[..n]),
+            Err(e) => defmt::warn!("remote: read failed: {}", defmt::Debug2Format(&e)),
+        }
+
+        while let Some((kind, payload)) = reader.next_packet() {
+            let reply = match Command::parse(kind, &payload) {
+                Ok(command) => {
+                    defmt::debug!("remote: {}", defmt::Debug2Format(&command));
+                    let main_window = main_window.clone();
+                    REPLY.reset();
+                    match slint::invoke_from_event_loop(move || execute(command, main_window)) {
+                        Ok(()) => REPLY.wait().await,
+                        Err(_) => Reply::Error(ErrorCode::NoWindow),
+                    }
+                }
+                Err(code) => Reply::Error(code),
+            };
+
+            let result = match reply {
+                Reply::Ok => link::write_packet(&mut tx, OK, &[]),
+                Reply::Value(value) => link::write_packet(&mut tx, VALUE, &[&value.to_le_bytes()]),
+                Reply::Error(code) => link::write_packet(&mut tx, ERROR, &[&[code as u8]]),
+            };
+            if let Err(e) = result {
+                defmt::warn!("remote: write failed: {}", defmt::Debug2Format(&e));
+            }
+        }
+    }
+}
+
+/// Run `command` on the UI loop and signal its reply
+fn execute(command: Command, main_window: slint::Weak<MainWindow>) {
+    let Some(main_window) = main_window.upgrade() else {
+        REPLY.signal(Reply::Error(ErrorCode::NoWindow));
+        return;
+    };
+    let window = main_window.window();
+    match command {
+        Command::Press(position) => {
+            // Like the touch bridge, hover first so that the touch areas see the pointer
+            window.dispatch_event(WindowEvent::PointerMoved { position });
+            window.dispatch_event(WindowEvent::PointerPressed {
+                position,
+                button: PointerEventButton::Left,
+            });
+        }
+        Command::Move(position) => window.dispatch_event(WindowEvent::PointerMoved { position }),
+        Command::Release(position) => {
+            window.dispatch_event(WindowEvent::PointerReleased {
+                position,
+                button: PointerEventButton::Left,
+            });
+            window.dispatch_event(WindowEvent::PointerExited);
+        }
+        Command::Key(text) => {
+            let text = slint::SharedString::from(text.as_str());
+            window.dispatch_event(WindowEvent::KeyPressed { text: text.clone() });
+            window.dispatch_event(WindowEvent::KeyReleased { text });
+        }
+        Command::Query(property) => {
+            REPLY.signal(Reply::Value(property.read(&main_window)));
+            return;
+        }
+        Command::WaitIdle => {
+            reply_when_idle(main_window.as_weak());
+            return;
+        }
+        Command::Screenshot => screenshot::request(),
+    }
+    REPLY.signal(Reply::Ok);
+}
+
+fn reply_when_idle(main_window: slint::Weak<MainWindow>) {
+    slint::Timer::single_shot(IDLE_POLL_PERIOD, move || match main_window.upgrade() {
+        Some(window) if window.window().has_active_animations() => reply_when_idle(main_window),
+        Some(_) => REPLY.signal(Reply::Ok),
+        None => REPLY.signal(Reply::Error(ErrorCode::NoWindow)),
+    });
+}
@@ -0,0 +1,41 @@
+//! Command and reply kinds of the remote control, the payloads are described in the parent module
+//!
+//! Also built into the host crate, which has the client, see `host/src/remote.rs`.
+
+pub const PRESS: u8 = 0x10;
+pub const MOVE: u8 = 0x11;
+pub const RELEASE: u8 = 0x12;
+pub const KEY: u8 = 0x13;
+pub const QUERY: u8 = 0x14;
+pub const WAIT_IDLE: u8 = 0x15;
+pub const SCREENSHOT: u8 = 0x16;
+
+pub const OK: u8 = 0x20;
+pub const VALUE: u8 = 0x21;
+pub const ERROR: u8 = 0x22;
+
+/// UI state readable with [`QUERY`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum Property {
+    /// `MainWindow.active-page`
+    ActivePage = 0,
+    /// Number of rows of `PrinterQueue.printer-queue`
+    QueueLength = 1,
+    /// `DemoPalette.night-mode`, 0 or 1
+    NightMode = 2,
+    /// `Calibration.active`, 0 or 1
+    CalibrationActive = 3,
+}
+
+impl Property {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Property::ActivePage),
+            1 => Some(Property::QueueLength),
+            2 => Some(Property::NightMode),
+            3 => Some(Property::CalibrationActive),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Screenshot capture over a serial link
+//!
+//! While a capture is running, [`DisplayWrapper`](crate::slint_ui::DisplayWrapper) copies every line it pushes to the
+//! display into a [`FrameSink`]. The frame is repainted in full for the capture, so the host gets the whole screen.
+//!
+//! Sent as [`link`](crate::link) packets:
+//!
+//! - [`BEGIN`]: `width:u16 height:u16 format:u8`, format 0 is RGB565
+//! - [`LINE`]: `y:u16 x:u16` followed by the pixels of the line segment
+//! - [`END`]: `lines:u16`, the number of line packets sent for the frame
+//!
+//! The host side is `host/src/bin/screenshot.rs`.
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use slint::platform::software_renderer::Rgb565Pixel;
+
+use crate::link;
+
+mod protocol;
+
+pub use protocol::{BEGIN, END, FORMAT_RGB565, LINE};
+
+/// Set to request a capture of the next frame
+///
+/// Exported so that it can be set from a debugger, e.g. `set var SCREENSHOT_REQUEST = 1` in gdb.
+#[no_mangle]
+static SCREENSHOT_REQUEST: AtomicBool = AtomicBool::new(false);
+
+/// Capture the next frame, can be called from any task or interrupt
+pub fn request() {
+    SCREENSHOT_REQUEST.store(true, Ordering::Release);
+}
+
+pub(crate) fn take_request() -> bool {
+    SCREENSHOT_REQUEST.swap(false, Ordering::AcqRel)
+}
+
+/// Receives the lines of a captured frame
+pub trait FrameSink {
+    fn begin(&mut self, width: u16, height: u16);
+    fn line(&mut self, y: u16, x: u16, pixels: &[Rgb565Pixel]);
+    fn end(&mut self);
+}
+
+/// Sends the captured frame over a byte stream, e.g. a UART
+pub struct ScreenshotWriter<W> {
+    out: W,
+    /// Pixels of the current line as bytes
+    bytes: Vec<u8>,
+    lines: u16,
+    /// Set after a write error, the rest of the frame is dropped
+    failed: bool,
+}
+
+impl<W: embedded_io::Write> ScreenshotWriter<W> {
+    pub fn new(out: W) -> Self {
+        ScreenshotWriter {
+            out,
+            bytes: Vec::new(),
+            lines: 0,
+            failed: false,
+        }
+    }
+
+    fn packet(&mut self, kind: u8, parts: &[&[u8]]) {
+        if self.failed {
+            return;
+        }
+        if let Err(e) = link::write_packet(&mut self.out, kind, parts) {
+            defmt::warn!("screenshot: write failed: {}", defmt::Debug2Format(&e));
+            self.failed = true;
+        }
+    }
+}
+
+impl<W: embedded_io::Write> FrameSink for ScreenshotWriter<W> {
+    fn begin(&mut self, width: u16, height: u16) {
+        self.lines = 0;
+        self.failed = false;
+        let [w0, w1] = width.to_le_bytes();
+        let [h0, h1] = height.to_le_bytes();
+        self.packet(BEGIN, &[&[w0, w1, h0, h1, FORMAT_RGB565]]);
+    }
+
+    fn line(&mut self, y: u16, x: u16, pixels: &[Rgb565Pixel]) {
+        let [y0, y1] = y.to_le_bytes();
+        let [x0, x1] = x.to_le_bytes();
+        let mut bytes = core::mem::take(&mut self.bytes);
+        bytes.clear();
+        bytes.extend(pixels.iter().flat_map(|p| p.0.to_le_bytes()));
+        self.packet(LINE, &[&[y0, y1, x0, x1], &bytes]);
+        self.bytes = bytes;
+        self.lines += 1;
+    }
+
+    fn end(&mut self) {
+        let lines = self.lines.to_le_bytes();
+        self.packet(END, &[&lines]);
+        if !self.failed {
+            let _ = self.out.flush();
+            defmt::info!("screenshot: sent {} lines", self.lines);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Packet kinds of the screenshots, the payloads are described in the parent module
+//!
+//! Also built into the host crate, which decodes the screenshots, see `host/src/capture.rs`.
+
+pub const BEGIN: u8 = 0x01;
+pub const LINE: u8 = 0x02;
+pub const END: u8 = 0x03;
+
+/// Pixel format of [`BEGIN`]
+pub const FORMAT_RGB565: u8 = 0;
@@ -0,0 +1,330 @@
+//! User settings, persisted in a reserved area of the XPI0 flash
+//!
+//! The area (`XPI0_SETTINGS` in `memory.x`) is a log of fixed size records, written one slot after the other so that
+//! every sector wears at the same rate. Each record carries a sequence number, the valid record with the latest one is
+//! the current settings. A sector is only erased when the writes reach it, and the area spans at least two sectors, so
+//! the previous record is always left intact: a reset while saving loses the new settings, never the old ones.
+//!
+//! Record layout, little endian:
+//!
+//! | offset  | size | content                                          |
+//! |---------|------|--------------------------------------------------|
+//! | 0       | 2    | [`MAGIC`]                                        |
+//! | 2       | 1    | format version of the payload                    |
+//! | 3       | 1    | payload length `n`                               |
+//! | 4       | 4    | sequence number                                  |
+//! | 8       | n    | payload, see [`Settings::encode`]                |
+//! | 8 + n   | 4    | CRC-32 (IEEE) of the bytes before                |
+//!
+//! The rest of the slot is left erased. Shared by the firmware and the host crate.
+
+use embedded_storage::nor_flash::NorFlash;
+
+/// Size of a record slot, a multiple of the write size of the flash
+pub const SLOT_SIZE: usize = 32;
+const MAGIC: u16 = 0x5453;
+/// Format version written by [`Settings::encode`]
+const FORMAT_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 8;
+const CRC_SIZE: usize = 4;
+/// Value of the erased flash
+const ERASED: u8 = 0xFF;
+/// Fixed point scale of the linear terms of the touch calibration, ±4 in an `i16`
+const CALIBRATION_SCALE: f32 = 8192.0;
+/// Fixed point scale of the offsets of the touch calibration, ±2048 pixels in an `i16`
+const CALIBRATION_OFFSET_SCALE: f32 = 16.0;
+
+/// The "Layout" setting
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Portrait,
+    Landscape,
+}
+
+/// The "Quality" setting
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Quality {
+    Best,
+    Medium,
+    Draft,
+}
+
+/// The "Color" setting
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Grayscale,
+    Color,
+}
+
+impl Layout {
+    const ALL: [Self; 2] = [Layout::Portrait, Layout::Landscape];
+
+    /// Index of the choice on the settings page
+    pub fn index(self) -> i32 {
+        Self::ALL.iter().position(|layout| *layout == self).unwrap() as i32
+    }
+
+    pub fn from_index(index: i32) -> Option<Self> {
+        Self::ALL.get(usize::try_from(index).ok()?).copied()
+    }
+}
+
+impl Quality {
+    const ALL: [Self; 3] = [Quality::Best, Quality::Medium, Quality::Draft];
+
+    /// Index of the choice on the settings page
+    pub fn index(self) -> i32 {
+        Self::ALL.iter().position(|quality| *quality == self).unwrap() as i32
+    }
+
+    pub fn from_index(index: i32) -> Option<Self> {
+        Self::ALL.get(usize::try_from(index).ok()?).copied()
+    }
+}
+
+impl ColorMode {
+    const ALL: [Self; 2] = [ColorMode::Grayscale, ColorMode::Color];
+
+    /// Index of the choice on the settings page
+    pub fn index(self) -> i32 {
+        Self::ALL.iter().position(|color| *color == self).unwrap() as i32
+    }
+
+    pub fn from_index(index: i32) -> Option<Self> {
+        Self::ALL.get(usize::try_from(index).ok()?).copied()
+    }
+}
+
+/// Everything that survives a reset, the defaults are those of the .slint files
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub night_mode: bool,
+    pub layout: Layout,
+    pub quality: Quality,
+    pub color: ColorMode,
+    pub eco_mode: bool,
+    pub turbo: bool,
+    /// First two rows of the matrix of the calibrated `TouchTransform`, `None` until the touch panel is calibrated
+    ///
+    /// Saved with a precision of 1/8192 for the linear terms and 1/16 pixel for the offsets.
+    pub touch_calibration: Option<[[f32; 3]; 2]>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            night_mode: false,
+            layout: Layout::Portrait,
+            quality: Quality::Best,
+            color: ColorMode::Grayscale,
+            eco_mode: false,
+            turbo: true,
+            touch_calibration: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Payload of the format version: a byte of flags, the layout, quality and color choices, then the touch
+    /// calibration as 6 fixed point `i16`, left zero when not calibrated
+    fn encode(&self) -> [u8; 16] {
+        let flags = self.night_mode as u8
+            | (self.eco_mode as u8) << 1
+            | (self.turbo as u8) << 2
+            | (self.touch_calibration.is_some() as u8) << 3;
+        let mut payload = [0; 16];
+        payload[..4].copy_from_slice(&[
+            flags,
+            Layout::ALL.iter().position(|l| *l == self.layout).unwrap() as u8,
+            Quality::ALL.iter().position(|q| *q == self.quality).unwrap() as u8,
+            ColorMode::ALL.iter().position(|c| *c == self.color).unwrap() as u8,
+        ]);
+        if let Some(rows) = &self.touch_calibration {
+            let values = rows.iter().flatten().enumerate();
+            for ((i, value), bytes) in values.zip(payload[4..].chunks_exact_mut(2)) {
+                bytes.copy_from_slice(&to_fixed(*value, calibration_scale(i)).to_le_bytes());
+            }
+        }
+        payload
+    }
+
+    /// `None` for the payloads of unknown versions, e.g. written by a newer firmware
+    fn decode(version: u8, payload: &[u8]) -> Option<Self> {
+        if (version, payload.len()) != (FORMAT_VERSION, 16) {
+            return None;
+        }
+        let [flags, layout, quality, color] = payload[..4] else {
+            return None;
+        };
+        let touch_calibration = (flags & 8 != 0).then(|| {
+            let mut rows = [[0.0; 3]; 2];
+            let values = rows.iter_mut().flatten().enumerate();
+            for ((i, value), bytes) in values.zip(payload[4..].chunks_exact(2)) {
+                *value = i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / calibration_scale(i);
+            }
+            rows
+        });
+        Some(Settings {
+            night_mode: flags & 1 != 0,
+            layout: *Layout::ALL.get(layout as usize)?,
+            quality: *Quality::ALL.get(quality as usize)?,
+            color: *ColorMode::ALL.get(color as usize)?,
+            eco_mode: flags & 2 != 0,
+            turbo: flags & 4 != 0,
+            touch_calibration,
+        })
+    }
+}
+
+/// Fixed point scale of the `i`th coefficient of the touch calibration, row by row
+fn calibration_scale(i: usize) -> f32 {
+    if i % 3 == 2 {
+        CALIBRATION_OFFSET_SCALE
+    } else {
+        CALIBRATION_SCALE
+    }
+}
+
+/// Round to the nearest, saturating, `f32::round` is not available in `core`
+fn to_fixed(value: f32, scale: f32) -> i16 {
+    let value = value * scale;
+    (if value < 0.0 { value - 0.5 } else { value + 0.5 }) as i16
+}
+
+/// CRC-32 with the polynomial of Ethernet and zlib
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Whether sequence number `a` comes after `b`, across the wrap of the counter
+///
+/// Serial number arithmetic: the records of the area are far less than 2^31 saves apart.
+fn is_newer(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+/// A record read back from a slot
+struct Record {
+    sequence: u32,
+    settings: Option<Settings>,
+}
+
+impl Record {
+    /// `None` if the slot doesn't hold a complete record, e.g. erased or interrupted while written
+    fn parse(slot: &[u8; SLOT_SIZE]) -> Option<Self> {
+        let len = slot[3] as usize;
+        if u16::from_le_bytes([slot[0], slot[1]]) != MAGIC || HEADER_SIZE + len + CRC_SIZE > SLOT_SIZE {
+            return None;
+        }
+        let end = HEADER_SIZE + len;
+        let crc = u32::from_le_bytes(slot[end..end + CRC_SIZE].try_into().unwrap());
+        if crc32(&slot[..end]) != crc {
+            return None;
+        }
+        Some(Record {
+            sequence: u32::from_le_bytes(slot[4..8].try_into().unwrap()),
+            settings: Settings::decode(slot[2], &slot[HEADER_SIZE..end]),
+        })
+    }
+}
+
+/// Reads and writes the [`Settings`] in an area of a NOR flash
+pub struct SettingsStore<F> {
+    flash: F,
+    /// Offset of the area in `flash`, aligned to the erase size
+    offset: u32,
+    slots: u32,
+    /// Slot of the next record
+    next_slot: u32,
+    /// Sequence number of the latest record
+    sequence: u32,
+}
+
+impl<F: NorFlash> SettingsStore<F> {
+    /// `offset` and `len` must be multiples of the erase size of `flash`, and span at least two sectors
+    pub fn new(flash: F, offset: u32, len: u32) -> Self {
+        let erase_size = F::ERASE_SIZE as u32;
+        assert!(offset % erase_size == 0 && len % erase_size == 0 && len >= 2 * erase_size);
+        assert!(SLOT_SIZE % F::WRITE_SIZE == 0 && SLOT_SIZE % F::READ_SIZE == 0 && F::ERASE_SIZE % SLOT_SIZE == 0);
+        SettingsStore {
+            flash,
+            offset,
+            slots: len / SLOT_SIZE as u32,
+            next_slot: 0,
+            sequence: 0,
+        }
+    }
+
+    fn read_slot(&mut self, slot: u32) -> Result<[u8; SLOT_SIZE], F::Error> {
+        let mut bytes = [0; SLOT_SIZE];
+        self.flash.read(self.offset + slot * SLOT_SIZE as u32, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Latest saved settings, `None` if there are none or they can't be read by this firmware
+    ///
+    /// Scans the whole area, call it once before the first [`save`](Self::save).
+    pub fn load(&mut self) -> Result<Option<Settings>, F::Error> {
+        let mut latest: Option<(u32, Record)> = None;
+        for slot in 0..self.slots {
+            let Some(record) = Record::parse(&self.read_slot(slot)?) else {
+                continue;
+            };
+            match &latest {
+                Some((_, l)) if !is_newer(record.sequence, l.sequence) => {}
+                _ => latest = Some((slot, record)),
+            }
+        }
+        Ok(latest.and_then(|(slot, record)| {
+            self.next_slot = (slot + 1) % self.slots;
+            self.sequence = record.sequence;
+            record.settings
+        }))
+    }
+
+    /// Append a record of `settings`, erasing the sector ahead when the writes reach it
+    pub fn save(&mut self, settings: &Settings) -> Result<(), F::Error> {
+        let slots_per_sector = (F::ERASE_SIZE / SLOT_SIZE) as u32;
+        let mut slot = self.next_slot;
+        loop {
+            if slot % slots_per_sector == 0 {
+                let from = self.offset + slot * SLOT_SIZE as u32;
+                self.flash.erase(from, from + F::ERASE_SIZE as u32)?;
+                break;
+            }
+            // Skip the leftovers of an interrupted write, they can't be written over before an erase
+            if self.read_slot(slot)?.iter().all(|b| *b == ERASED) {
+                break;
+            }
+            slot = (slot + 1) % self.slots;
+        }
+
+        let payload = settings.encode();
+        let sequence = self.sequence.wrapping_add(1);
+        let mut record = [ERASED; SLOT_SIZE];
+        record[..2].copy_from_slice(&MAGIC.to_le_bytes());
+        record[2] = FORMAT_VERSION;
+        record[3] = payload.len() as u8;
+        record[4..8].copy_from_slice(&sequence.to_le_bytes());
+        let end = HEADER_SIZE + payload.len();
+        record[HEADER_SIZE..end].copy_from_slice(&payload);
+        let crc = crc32(&record[..end]);
+        record[end..end + CRC_SIZE].copy_from_slice(&crc.to_le_bytes());
+        self.flash.write(self.offset + slot * SLOT_SIZE as u32, &record)?;
+
+        self.next_slot = (slot + 1) % self.slots;
+        self.sequence = sequence;
+        Ok(())
+    }
+}
@@ -2,21 +2,80 @@
 //!
 
 extern crate alloc;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
 use alloc::rc::Rc;
+use core::cell::{Cell, RefCell};
+use core::future::Future;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
+use embassy_futures::select::{select3, Either3};
+use embassy_futures::yield_now;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
 use embedded_graphics_core::pixelcolor::raw::RawU16;
 use embedded_graphics_core::prelude::*;
 use embedded_graphics_core::primitives::Rectangle;
-use slint::platform::software_renderer::MinimalSoftwareWindow;
-use slint::platform::Platform;
+use slint::platform::software_renderer::{MinimalSoftwareWindow, RenderingRotation, RepaintBufferType, Rgb565Pixel};
+use slint::platform::{EventLoopProxy, Platform};
+use slint::{ComponentHandle as _, EventLoopError, PhysicalSize};
+
+use crate::profiler::{self, FrameStats, Profiler};
+use crate::screenshot::{self, FrameSink};
+use crate::touch::{TouchBridge, TOUCH_POLL_PERIOD, TOUCH_REPORTS};
 
 slint::include_modules!();
 
-pub struct MyPlatform {
-    pub window: Rc<MinimalSoftwareWindow>,
+enum LoopEvent {
+    Invoke(Box<dyn FnOnce() + Send>),
+    Quit,
+}
+
+/// Events posted through [`MyEventLoopProxy`], from any task or interrupt
+static LOOP_EVENTS: Mutex<CriticalSectionRawMutex, RefCell<VecDeque<LoopEvent>>> =
+    Mutex::new(RefCell::new(VecDeque::new()));
+/// Wakes the UI loop once something is pushed to [`LOOP_EVENTS`]
+static LOOP_WAKE: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+fn post_event(event: LoopEvent) {
+    LOOP_EVENTS.lock(|events| events.borrow_mut().push_back(event));
+    LOOP_WAKE.signal(());
+}
+
+/// Dispatch `event` to the window from another task, it runs on the UI loop
+pub fn post_window_event(main_window: &slint::Weak<MainWindow>, event: slint::platform::WindowEvent) {
+    let main_window = main_window.clone();
+    post_event(LoopEvent::Invoke(Box::new(move || {
+        if let Some(main_window) = main_window.upgrade() {
+            main_window.window().dispatch_event(event);
+        }
+    })));
+}
+
+/// Pop the next posted event, the lock is not held while it runs
+fn next_event() -> Option<LoopEvent> {
+    LOOP_EVENTS.lock(|events| events.borrow_mut().pop_front())
 }
 
-impl Platform for MyPlatform {
+pub struct MyPlatform<D> {
+    window: Rc<MinimalSoftwareWindow>,
+    event_loop: Rc<EventLoop<D>>,
+}
+
+impl<D> MyPlatform<D> {
+    pub fn new(window: Rc<MinimalSoftwareWindow>, event_loop: Rc<EventLoop<D>>) -> Self {
+        MyPlatform { window, event_loop }
+    }
+}
+
+impl<D> Platform for MyPlatform<D>
+where
+    D: DrawTarget<Color = embedded_graphics_core::pixelcolor::Rgb565> + 'static,
+    D::Error: defmt::Format,
+{
     fn create_window_adapter(&self) -> Result<Rc<dyn slint::platform::WindowAdapter>, slint::PlatformError> {
         // Since on MCUs, there can be only one window, just return a clone of self.window.
         // We'll also use the same window in the event loop.
@@ -26,18 +85,269 @@ impl Platform for MyPlatform {
         core::time::Duration::from_micros(embassy_time::Instant::now().as_micros())
     }
 
+    /// Backs `MainWindow::run()`, blocks the executor: the other tasks don't run until the loop quits
+    ///
+    /// The firmware awaits [`EventLoop::run`] from its UI task instead, this is kept for single task setups.
     fn run_event_loop(&self) -> Result<(), slint::PlatformError> {
+        block_on(self.event_loop.run());
         Ok(())
     }
+
+    fn new_event_loop_proxy(&self) -> Option<Box<dyn EventLoopProxy>> {
+        Some(Box::new(MyEventLoopProxy))
+    }
+}
+
+/// The UI side of the firmware: renders the window and feeds it the reports of the touch task
+pub struct EventLoop<D> {
+    window: Rc<MinimalSoftwareWindow>,
+    display: RefCell<D>,
+    /// Native size of the display, the lines are pushed in its scan direction whatever the rotation
+    panel_size: Size,
+    rotation: Cell<RenderingRotation>,
+    /// Set when the display no longer holds the previous frame, the next one is repainted in full
+    repaint_all: Cell<bool>,
+    touch_bridge: RefCell<TouchBridge>,
+    profiler: RefCell<Profiler>,
+    stats_handler: RefCell<Option<Box<dyn FnMut(&FrameStats)>>>,
+    screenshot: RefCell<Option<Box<dyn FrameSink>>>,
+    display_error_handler: RefCell<Option<Box<dyn FnMut(&mut D)>>>,
 }
 
-pub struct DisplayWrapper<'a, T> {
+impl<D> EventLoop<D>
+where
+    D: DrawTarget<Color = embedded_graphics_core::pixelcolor::Rgb565>,
+    D::Error: defmt::Format,
+{
+    pub fn new(window: Rc<MinimalSoftwareWindow>, display: D, touch_bridge: TouchBridge, profiler: Profiler) -> Self {
+        EventLoop {
+            window,
+            panel_size: display.bounding_box().size,
+            display: RefCell::new(display),
+            rotation: Cell::new(RenderingRotation::NoRotation),
+            repaint_all: Cell::new(false),
+            touch_bridge: RefCell::new(touch_bridge),
+            profiler: RefCell::new(profiler),
+            stats_handler: RefCell::new(None),
+            screenshot: RefCell::new(None),
+            display_error_handler: RefCell::new(None),
+        }
+    }
+
+    /// Called with the frame timing stats once per report period
+    pub fn on_stats(&self, handler: impl FnMut(&FrameStats) + 'static) {
+        *self.stats_handler.borrow_mut() = Some(Box::new(handler));
+    }
+
+    /// Where the frames are sent when a screenshot is requested with [`screenshot::request`]
+    pub fn set_screenshot_sink(&self, sink: impl FrameSink + 'static) {
+        *self.screenshot.borrow_mut() = Some(Box::new(sink));
+    }
+
+    /// Called after a frame was aborted by a display error, to bring the display back
+    ///
+    /// The next frame is repainted in full either way.
+    pub fn on_display_error(&self, handler: impl FnMut(&mut D) + 'static) {
+        *self.display_error_handler.borrow_mut() = Some(Box::new(handler));
+    }
+
+    /// Rotate the UI in software, for panels that can't change their scan direction
+    ///
+    /// The window is resized to the rotated panel. The touch transform has to follow, see
+    /// [`TouchTransform::rotate`](crate::touch::TouchTransform::rotate).
+    pub fn set_rotation(&self, rotation: RenderingRotation) {
+        self.rotation.set(rotation);
+        let Size { width, height } = self.panel_size;
+        let size = match rotation {
+            RenderingRotation::Rotate90 | RenderingRotation::Rotate270 => PhysicalSize::new(height, width),
+            _ => PhysicalSize::new(width, height),
+        };
+        self.window.set_size(size);
+        self.repaint_all.set(true);
+    }
+
+    /// Run until `slint::quit_event_loop` is called
+    pub async fn run(&self) {
+        let mut display = self.display.borrow_mut();
+        let mut touch_bridge = self.touch_bridge.borrow_mut();
+        let mut profiler = self.profiler.borrow_mut();
+        let mut screenshot = self.screenshot.borrow_mut();
+        let mut line_buffer = [Rgb565Pixel::default(); 536];
+        let mut touch_report = None;
+
+        loop {
+            profiler.loop_iteration();
+
+            // Posted from the other tasks, handled on every iteration, animations or not. The wake-up of anything
+            // posted from here on is kept for the wait below.
+            LOOP_WAKE.reset();
+            while let Some(event) = next_event() {
+                match event {
+                    LoopEvent::Invoke(f) => f(),
+                    LoopEvent::Quit => return,
+                }
+            }
+
+            // Let Slint run the timer hooks and update animations.
+            slint::platform::update_timers_and_animations();
+
+            // Forward the reports of the touch task, an empty poll lets the bridge detect the release
+            let reports = touch_report.take().into_iter();
+            for report in reports.chain(core::iter::from_fn(|| TOUCH_REPORTS.try_receive().ok())) {
+                touch_bridge.process(Some(report.point), &self.window);
+                profiler.input_dispatched(Instant::now() - report.read_at);
+            }
+            touch_bridge.process(None, &self.window);
+
+            let capture = screenshot::take_request();
+            if capture && screenshot.is_none() {
+                defmt::warn!("screenshot: no sink set");
+            }
+            let repaint_all = self.repaint_all.get();
+            if capture || repaint_all {
+                self.window.request_redraw();
+            }
+
+            // Draw the scene if something needs to be drawn.
+            let mut display_error = None;
+            let drawn = self.window.draw_if_needed(|renderer| {
+                let mut sink = screenshot.as_deref_mut().filter(|_| capture);
+                // Screenshots and recovered displays need the whole frame, not only the dirty region
+                let full_frame = sink.is_some() || repaint_all;
+                if full_frame {
+                    renderer.set_repaint_buffer_type(RepaintBufferType::NewBuffer);
+                }
+                renderer.set_rendering_rotation(self.rotation.get());
+                if let Some(sink) = sink.as_mut() {
+                    sink.begin(self.panel_size.width as u16, self.panel_size.height as u16);
+                }
+
+                let start = profiler::cycles();
+                // Use single line buffer
+                let mut wrapper = DisplayWrapper {
+                    display: &mut *display,
+                    line_buffer: &mut line_buffer,
+                    profiler: Some(&mut *profiler),
+                    screenshot: sink.as_deref_mut(),
+                    error: None,
+                };
+                renderer.render_by_line(&mut wrapper);
+                display_error = wrapper.error.take();
+                profiler.frame_done(profiler::cycles() - start);
+
+                if let Some(sink) = sink {
+                    sink.end();
+                }
+                if full_frame {
+                    renderer.set_repaint_buffer_type(RepaintBufferType::ReusedBuffer);
+                }
+            });
+
+            if drawn {
+                self.repaint_all.set(display_error.is_some());
+            }
+            if let Some(error) = display_error {
+                defmt::warn!("display: frame aborted: {}", error);
+                if let Some(handler) = self.display_error_handler.borrow_mut().as_mut() {
+                    handler(&mut *display);
+                }
+            }
+
+            if let Some(stats) = profiler.poll_report() {
+                if let Some(handler) = self.stats_handler.borrow_mut().as_mut() {
+                    handler(&stats);
+                }
+            }
+
+            if self.window.has_active_animations() {
+                // Let the other tasks run between frames
+                yield_now().await;
+                continue;
+            }
+
+            // Wait for the next timer, a touch or an event posted from outside
+            let mut timeout = slint::platform::duration_until_next_timer_update()
+                .map(|d| Duration::from_micros(d.as_micros() as u64));
+            if touch_bridge.is_active() {
+                // Keep polling while touched, release and long-press are detected from the lack of reports
+                timeout = Some(timeout.map_or(TOUCH_POLL_PERIOD, |t| t.min(TOUCH_POLL_PERIOD)));
+            }
+            let timer = async {
+                match timeout {
+                    Some(timeout) => Timer::after(timeout).await,
+                    None => core::future::pending().await,
+                }
+            };
+            if let Either3::Second(report) = select3(timer, TOUCH_REPORTS.receive(), LOOP_WAKE.wait()).await {
+                touch_report = Some(report);
+            }
+        }
+    }
+}
+
+/// Set by the waker of [`block_on`], so that a wake-up between the poll and WFI is not lost
+static WOKEN: AtomicBool = AtomicBool::new(false);
+
+static WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |_| RawWaker::new(core::ptr::null(), &WAKER_VTABLE),
+    |_| WOKEN.store(true, Ordering::Release),
+    |_| WOKEN.store(true, Ordering::Release),
+    |_| {},
+);
+
+/// Run `fut` to completion, sleeping with WFI while it is pending
+///
+/// Any interrupt wakes the core up: the timer alarm, or an interrupt handler posting to [`LOOP_EVENTS`].
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = core::pin::pin!(fut);
+    let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &WAKER_VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+            return output;
+        }
+        // WFI still returns on a pending interrupt when they are masked, it is serviced once `free` returns
+        riscv::interrupt::free(|| {
+            if !WOKEN.swap(false, Ordering::AcqRel) {
+                riscv::asm::wfi();
+            }
+        });
+    }
+}
+
+/// Posts closures and quit requests to [`EventLoop`]
+///
+/// Backs `slint::invoke_from_event_loop` and `Weak::upgrade_in_event_loop`, so other embassy tasks can update the
+/// UI models without touching the window themselves.
+pub struct MyEventLoopProxy;
+
+impl EventLoopProxy for MyEventLoopProxy {
+    fn quit_event_loop(&self) -> Result<(), EventLoopError> {
+        post_event(LoopEvent::Quit);
+        Ok(())
+    }
+
+    fn invoke_from_event_loop(&self, event: Box<dyn FnOnce() + Send>) -> Result<(), EventLoopError> {
+        post_event(LoopEvent::Invoke(event));
+        Ok(())
+    }
+}
+
+/// Pushes the rendered lines to the display, pass it to `render_by_line` by reference
+pub struct DisplayWrapper<'a, T: DrawTarget> {
     pub display: &'a mut T,
     pub line_buffer: &'a mut [slint::platform::software_renderer::Rgb565Pixel],
+    /// Accounts the time spent pushing lines to the display
+    pub profiler: Option<&'a mut Profiler>,
+    /// Receives a copy of the lines while a screenshot is captured
+    pub screenshot: Option<&'a mut dyn FrameSink>,
+    /// First error of the frame, the remaining lines are skipped once it is set
+    pub error: Option<T::Error>,
 }
 
 impl<T: DrawTarget<Color = embedded_graphics_core::pixelcolor::Rgb565>>
-    slint::platform::software_renderer::LineBufferProvider for DisplayWrapper<'_, T>
+    slint::platform::software_renderer::LineBufferProvider for &mut DisplayWrapper<'_, T>
 {
     type TargetPixel = slint::platform::software_renderer::Rgb565Pixel;
     fn process_line(
@@ -46,16 +356,29 @@ impl<T: DrawTarget<Color = embedded_graphics_core::pixelcolor::Rgb565>>
         range: core::ops::Range<usize>,
         render_fn: impl FnOnce(&mut [Self::TargetPixel]),
     ) {
+        if self.error.is_some() {
+            return;
+        }
+
         // Render into the line
         render_fn(&mut self.line_buffer[range.clone()]);
 
         // Send the line to the screen using DrawTarget::fill_contiguous
-        self.display
-            .fill_contiguous(
-                &Rectangle::new(Point::new(range.start as _, line as _), Size::new(range.len() as _, 1)),
-                self.line_buffer[range.clone()].iter().map(|p| RawU16::new(p.0).into()),
-            )
-            .map_err(drop)
-            .unwrap();
+        let start = profiler::cycles();
+        let result = self.display.fill_contiguous(
+            &Rectangle::new(Point::new(range.start as _, line as _), Size::new(range.len() as _, 1)),
+            self.line_buffer[range.clone()].iter().map(|p| RawU16::new(p.0).into()),
+        );
+        if let Err(e) = result {
+            self.error = Some(e);
+            return;
+        }
+        let elapsed = profiler::cycles() - start;
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.line_flushed(elapsed);
+        }
+        if let Some(screenshot) = self.screenshot.as_mut() {
+            screenshot.line(line as u16, range.start as u16, &self.line_buffer[range]);
+        }
     }
 }
@@ -0,0 +1,113 @@
+//! Translates touch controller reports into Slint window events
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use core::cell::Cell;
+
+use embassy_time::Instant;
+use slint::platform::{PointerEventButton, WindowEvent};
+
+use super::filter::{FilterConfig, TouchEvent, TouchFilter};
+use super::gesture::{GestureConfig, TapClassifier, TapGesture};
+use super::{PointEvent, TouchTransform};
+
+pub struct TouchBridge {
+    /// Shared with the calibration page, which replaces it once calibrated
+    transform: Rc<Cell<TouchTransform>>,
+    filter: TouchFilter,
+    classifier: TapClassifier,
+    gesture_handler: Option<Box<dyn FnMut(TapGesture)>>,
+    /// The primary press was cancelled by a long-press, drop the moves until the release
+    swallow_until_release: bool,
+}
+
+impl TouchBridge {
+    pub fn new(transform: Rc<Cell<TouchTransform>>, filter: FilterConfig, gestures: GestureConfig) -> Self {
+        TouchBridge {
+            transform,
+            filter: TouchFilter::new(filter),
+            classifier: TapClassifier::new(gestures),
+            gesture_handler: None,
+            swallow_until_release: false,
+        }
+    }
+
+    /// Called for every long-press and hold
+    pub fn on_gesture(&mut self, handler: impl FnMut(TapGesture) + 'static) {
+        self.gesture_handler = Some(Box::new(handler));
+    }
+
+    /// True while a touch is in progress and the controller has to be polled
+    pub fn is_active(&self) -> bool {
+        self.filter.is_pressed()
+    }
+
+    /// Dispatch the pointer event matching `report` to `window`
+    ///
+    /// Called with `None` when there is no new report, so that release and long-press are detected in time.
+    pub fn process(&mut self, report: Option<PointEvent>, window: &slint::Window) {
+        let sample = report.map(|point| {
+            let position = self.transform.get().to_logical(point.x, point.y);
+            defmt::debug!("Point: {:?} -> ({}, {})", point, position.x, position.y);
+            (point.event, position)
+        });
+
+        let now = Instant::now();
+        let event = self.filter.update(now, sample);
+        let gesture = self.classifier.update(now, event);
+
+        match event {
+            Some(TouchEvent::Released(_)) => {
+                // Still sent after a long-press: the cancelled press doesn't click, but the `pointer-event` handlers,
+                // e.g. of the calibration page, see the finger lift
+                self.swallow_until_release = false;
+                Self::dispatch(window, event);
+            }
+            _ if self.swallow_until_release => {}
+            _ => Self::dispatch(window, event),
+        }
+
+        if let Some(gesture) = gesture {
+            if let TapGesture::LongPress(position) = gesture {
+                if self.classifier.config().long_press_as_secondary_click {
+                    // Cancel the primary press so that lifting the finger doesn't click
+                    window.dispatch_event(WindowEvent::PointerExited);
+                    window.dispatch_event(WindowEvent::PointerPressed {
+                        position,
+                        button: PointerEventButton::Right,
+                    });
+                    window.dispatch_event(WindowEvent::PointerReleased {
+                        position,
+                        button: PointerEventButton::Right,
+                    });
+                    self.swallow_until_release = true;
+                }
+            }
+            if let Some(handler) = self.gesture_handler.as_mut() {
+                handler(gesture);
+            }
+        }
+    }
+
+    fn dispatch(window: &slint::Window, event: Option<TouchEvent>) {
+        match event {
+            Some(TouchEvent::Pressed(position)) => {
+                // Touch panels have no hover, move there first so `has-hover` follows the finger
+                window.dispatch_event(WindowEvent::PointerMoved { position });
+                window.dispatch_event(WindowEvent::PointerPressed {
+                    position,
+                    button: PointerEventButton::Left,
+                });
+            }
+            Some(TouchEvent::Moved(position)) => window.dispatch_event(WindowEvent::PointerMoved { position }),
+            Some(TouchEvent::Released(position)) => {
+                window.dispatch_event(WindowEvent::PointerReleased {
+                    position,
+                    button: PointerEventButton::Left,
+                });
+                window.dispatch_event(WindowEvent::PointerExited);
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,9 @@
+//! Touch event flag of the controllers, shared with the host crate
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum EventType {
+    PressDown = 0b00,
+    LiftUp = 0b01,
+    Contact = 0b10,
+}
@@ -0,0 +1,278 @@
+//! Touch signal filtering and press/release state machine
+
+use embassy_time::{Duration, Instant};
+use slint::LogicalPosition;
+
+use super::EventType;
+
+/// Maximum window of the moving average filter
+pub const MAX_AVERAGE_WINDOW: usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Smoothing {
+    /// Forward the samples as they are
+    None,
+    /// Average of the last `window` samples, clamped to [`MAX_AVERAGE_WINDOW`]
+    MovingAverage { window: usize },
+    /// 1€ filter, see <https://gery.casiez.net/1euro/>
+    OneEuro {
+        /// Cutoff frequency at low speed, in Hz
+        min_cutoff: f32,
+        /// Speed coefficient, higher values reduce lag on fast moves
+        beta: f32,
+        /// Cutoff frequency of the speed estimation, in Hz
+        d_cutoff: f32,
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FilterConfig {
+    pub smoothing: Smoothing,
+    /// Moves closer than this to the press position are dropped until the finger leaves it, in logical pixels
+    pub tap_slop: f32,
+    /// The pointer is released when the controller reports nothing for this long
+    pub release_timeout: Duration,
+    /// Presses this close after a release are ignored
+    pub debounce: Duration,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        FilterConfig {
+            smoothing: Smoothing::MovingAverage { window: 3 },
+            tap_slop: 6.0,
+            release_timeout: Duration::from_millis(60),
+            debounce: Duration::from_millis(20),
+        }
+    }
+}
+
+/// Filtered pointer event, in screen coordinates
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TouchEvent {
+    Pressed(LogicalPosition),
+    Moved(LogicalPosition),
+    Released(LogicalPosition),
+}
+
+#[derive(Copy, Clone, Debug)]
+enum State {
+    Idle {
+        released_at: Option<Instant>,
+    },
+    Pressed {
+        origin: LogicalPosition,
+        last: LogicalPosition,
+        last_seen: Instant,
+        /// Set once the finger left the tap slop
+        dragging: bool,
+    },
+}
+
+pub struct TouchFilter {
+    config: FilterConfig,
+    state: State,
+    smoother: Smoother,
+}
+
+impl TouchFilter {
+    pub fn new(config: FilterConfig) -> Self {
+        TouchFilter {
+            config,
+            state: State::Idle { released_at: None },
+            smoother: Smoother::new(config.smoothing),
+        }
+    }
+
+    /// Feed one sample, `None` if the controller reported no touch
+    pub fn update(&mut self, now: Instant, sample: Option<(EventType, LogicalPosition)>) -> Option<TouchEvent> {
+        match (self.state, sample) {
+            (State::Idle { released_at }, Some((EventType::PressDown | EventType::Contact, position))) => {
+                if released_at.is_some_and(|t| now.saturating_duration_since(t) < self.config.debounce) {
+                    return None;
+                }
+                self.smoother.reset(now, position);
+                self.state = State::Pressed {
+                    origin: position,
+                    last: position,
+                    last_seen: now,
+                    dragging: false,
+                };
+                Some(TouchEvent::Pressed(position))
+            }
+            (State::Idle { .. }, _) => None,
+            (State::Pressed { last, .. }, Some((EventType::LiftUp, _))) => self.release(now, last),
+            (
+                State::Pressed {
+                    origin,
+                    last,
+                    dragging,
+                    ..
+                },
+                Some((_, position)),
+            ) => {
+                let position = self.smoother.filter(now, position);
+                let slop = self.config.tap_slop;
+                let dragging = dragging || distance_squared(origin, position) >= slop * slop;
+                self.state = State::Pressed {
+                    origin,
+                    last: if dragging { position } else { last },
+                    last_seen: now,
+                    dragging,
+                };
+                dragging.then_some(TouchEvent::Moved(position))
+            }
+            (State::Pressed { last, last_seen, .. }, None) => {
+                if now.saturating_duration_since(last_seen) >= self.config.release_timeout {
+                    self.release(now, last)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// True while a finger is on the panel
+    pub fn is_pressed(&self) -> bool {
+        matches!(self.state, State::Pressed { .. })
+    }
+
+    fn release(&mut self, now: Instant, position: LogicalPosition) -> Option<TouchEvent> {
+        self.state = State::Idle { released_at: Some(now) };
+        Some(TouchEvent::Released(position))
+    }
+}
+
+fn distance_squared(a: LogicalPosition, b: LogicalPosition) -> f32 {
+    let (dx, dy) = (a.x - b.x, a.y - b.y);
+    dx * dx + dy * dy
+}
+
+enum Smoother {
+    None,
+    MovingAverage {
+        window: usize,
+        samples: [LogicalPosition; MAX_AVERAGE_WINDOW],
+        len: usize,
+        next: usize,
+    },
+    OneEuro {
+        min_cutoff: f32,
+        beta: f32,
+        d_cutoff: f32,
+        x: OneEuroAxis,
+        y: OneEuroAxis,
+        last: Instant,
+    },
+}
+
+impl Smoother {
+    fn new(smoothing: Smoothing) -> Self {
+        match smoothing {
+            Smoothing::None => Smoother::None,
+            Smoothing::MovingAverage { window } => Smoother::MovingAverage {
+                window: window.clamp(1, MAX_AVERAGE_WINDOW),
+                samples: [LogicalPosition::new(0.0, 0.0); MAX_AVERAGE_WINDOW],
+                len: 0,
+                next: 0,
+            },
+            Smoothing::OneEuro {
+                min_cutoff,
+                beta,
+                d_cutoff,
+            } => Smoother::OneEuro {
+                min_cutoff,
+                beta,
+                d_cutoff,
+                x: OneEuroAxis::default(),
+                y: OneEuroAxis::default(),
+                last: Instant::from_ticks(0),
+            },
+        }
+    }
+
+    fn reset(&mut self, now: Instant, position: LogicalPosition) {
+        match self {
+            Smoother::None => {}
+            Smoother::MovingAverage {
+                window,
+                samples,
+                len,
+                next,
+            } => {
+                samples[0] = position;
+                *len = 1;
+                *next = 1 % *window;
+            }
+            Smoother::OneEuro { x, y, last, .. } => {
+                *x = OneEuroAxis::start(position.x);
+                *y = OneEuroAxis::start(position.y);
+                *last = now;
+            }
+        }
+    }
+
+    fn filter(&mut self, now: Instant, position: LogicalPosition) -> LogicalPosition {
+        match self {
+            Smoother::None => position,
+            Smoother::MovingAverage {
+                window,
+                samples,
+                len,
+                next,
+            } => {
+                samples[*next] = position;
+                *next = (*next + 1) % *window;
+                *len = (*len + 1).min(*window);
+                let sum = samples[..*len]
+                    .iter()
+                    .fold((0.0, 0.0), |(x, y), p| (x + p.x, y + p.y));
+                LogicalPosition {
+                    x: sum.0 / *len as f32,
+                    y: sum.1 / *len as f32,
+                }
+            }
+            Smoother::OneEuro {
+                min_cutoff,
+                beta,
+                d_cutoff,
+                x,
+                y,
+                last,
+            } => {
+                let dt = (now.saturating_duration_since(*last).as_micros() as f32 / 1_000_000.0).max(1e-3);
+                *last = now;
+                LogicalPosition {
+                    x: x.filter(position.x, dt, *min_cutoff, *beta, *d_cutoff),
+                    y: y.filter(position.y, dt, *min_cutoff, *beta, *d_cutoff),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct OneEuroAxis {
+    value: f32,
+    speed: f32,
+}
+
+impl OneEuroAxis {
+    fn start(value: f32) -> Self {
+        OneEuroAxis { value, speed: 0.0 }
+    }
+
+    fn filter(&mut self, value: f32, dt: f32, min_cutoff: f32, beta: f32, d_cutoff: f32) -> f32 {
+        let speed = (value - self.value) / dt;
+        self.speed += smoothing_factor(d_cutoff, dt) * (speed - self.speed);
+        let abs_speed = if self.speed < 0.0 { -self.speed } else { self.speed };
+        let cutoff = min_cutoff + beta * abs_speed;
+        self.value += smoothing_factor(cutoff, dt) * (value - self.value);
+        self.value
+    }
+}
+
+fn smoothing_factor(cutoff: f32, dt: f32) -> f32 {
+    let tau = 1.0 / (2.0 * core::f32::consts::PI * cutoff);
+    1.0 / (1.0 + tau / dt)
+}
@@ -0,0 +1,104 @@
+//! Tap classification on top of the filtered touch events
+//!
+//! Double taps are left to Slint: the primary presses are forwarded, and the `TouchArea` reports them as
+//! `double-clicked`.
+
+use embassy_time::{Duration, Instant};
+use slint::LogicalPosition;
+
+use super::filter::TouchEvent;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GestureConfig {
+    /// Time a finger has to stay still before a long-press is reported
+    pub long_press: Duration,
+    /// Repeat period of [`TapGesture::Hold`] once the long-press fired, `None` disables it
+    pub hold_repeat: Option<Duration>,
+    /// Deliver long-press to Slint as a secondary button click, cancelling the primary press
+    pub long_press_as_secondary_click: bool,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            long_press: Duration::from_millis(600),
+            hold_repeat: Some(Duration::from_millis(150)),
+            long_press_as_secondary_click: true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TapGesture {
+    LongPress(LogicalPosition),
+    /// Repeated while the finger stays down after a long-press
+    Hold(LogicalPosition),
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Press {
+    position: LogicalPosition,
+    /// Time of the press, or of the last long-press/hold report
+    since: Instant,
+    long_pressed: bool,
+    moved: bool,
+}
+
+pub struct TapClassifier {
+    config: GestureConfig,
+    press: Option<Press>,
+}
+
+impl TapClassifier {
+    pub fn new(config: GestureConfig) -> Self {
+        TapClassifier { config, press: None }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Feed the filtered event of this poll, called on every poll so that long-press fires without new samples
+    pub fn update(&mut self, now: Instant, event: Option<TouchEvent>) -> Option<TapGesture> {
+        match event {
+            Some(TouchEvent::Pressed(position)) => {
+                self.press = Some(Press {
+                    position,
+                    since: now,
+                    long_pressed: false,
+                    moved: false,
+                });
+                None
+            }
+            Some(TouchEvent::Moved(_)) => {
+                if let Some(press) = self.press.as_mut() {
+                    press.moved = true;
+                }
+                None
+            }
+            Some(TouchEvent::Released(_)) => {
+                self.press = None;
+                None
+            }
+            None => {
+                let press = self.press.as_mut().filter(|p| !p.moved)?;
+                let elapsed = now.saturating_duration_since(press.since);
+                if !press.long_pressed {
+                    if elapsed < self.config.long_press {
+                        return None;
+                    }
+                    press.long_pressed = true;
+                    press.since = now;
+                    Some(TapGesture::LongPress(press.position))
+                } else {
+                    let repeat = self.config.hold_repeat?;
+                    if elapsed < repeat {
+                        return None;
+                    }
+                    press.since = now;
+                    Some(TapGesture::Hold(press.position))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Touch controller abstraction
+//!
+//! Every touch IC driver implements [`TouchController`], so the Slint event bridge does not care which panel is
+//! fitted.
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::{Duration, Instant};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+
+mod bridge;
+mod event;
+mod filter;
+mod gesture;
+mod transform;
+
+pub use bridge::TouchBridge;
+pub use event::EventType;
+pub use filter::{FilterConfig, Smoothing};
+pub use gesture::{GestureConfig, TapGesture};
+
+/// Poll period of the touch controller while a finger is on the panel
+pub const TOUCH_POLL_PERIOD: Duration = Duration::from_millis(10);
+
+/// A touch point as posted by the touch task
+#[derive(Copy, Clone, Debug)]
+pub struct TouchReport {
+    pub point: PointEvent,
+    /// When the point was read from the controller, to measure the input latency
+    pub read_at: Instant,
+}
+
+/// Reports read by the touch task, drained by the UI event loop
+pub static TOUCH_REPORTS: Channel<CriticalSectionRawMutex, TouchReport, 8> = Channel::new();
+pub use transform::{CalibrationPoint, TouchTransform};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+#[repr(u8)]
+pub enum Gesture {
+    MoveUp = 0x10,
+    MoveRight = 0x14,
+    MoveDown = 0x18,
+    MoveLeft = 0x1C,
+    ZoomIn = 0x48,
+    ZoomOut = 0x49,
+}
+
+impl Gesture {
+    pub fn gesture_id(&self) -> u8 {
+        match self {
+            Gesture::MoveUp => 0x10,
+            Gesture::MoveRight => 0x14,
+            Gesture::MoveDown => 0x18,
+            Gesture::MoveLeft => 0x1C,
+            Gesture::ZoomIn => 0x48,
+            Gesture::ZoomOut => 0x49,
+        }
+    }
+
+    #[cfg(any(feature = "ft6236", feature = "cst816s"))]
+    pub(crate) fn from_u8(gesture: u8) -> Option<Self> {
+        match gesture {
+            0x10 => Some(Gesture::MoveUp),
+            0x14 => Some(Gesture::MoveRight),
+            0x18 => Some(Gesture::MoveDown),
+            0x1C => Some(Gesture::MoveLeft),
+            0x48 => Some(Gesture::ZoomIn),
+            0x49 => Some(Gesture::ZoomOut),
+            // 0x00 => None,
+            _ => None,
+        }
+    }
+}
+
+impl EventType {
+    #[cfg(any(feature = "ft6236", feature = "cst816s"))]
+    pub(crate) fn from_u8(event: u8) -> Option<Self> {
+        match event {
+            0b00 => Some(EventType::PressDown),
+            0b01 => Some(EventType::LiftUp),
+            0b10 => Some(EventType::Contact),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, defmt::Format)]
+pub struct PointEvent {
+    /// Touch X position
+    pub x: u16,
+    /// Touch Y position
+    pub y: u16,
+    /// Touch event flag
+    pub event: EventType,
+    /// Touch weight, 0 if the controller doesn't report it
+    pub weight: u8,
+    /// Touch area, 0 if the controller doesn't report it
+    pub area: u8,
+    /// Touch ID
+    pub touch_id: u8,
+}
+
+/// Errors of the touch controller drivers
+#[derive(Copy, Clone, Eq, PartialEq, Debug, defmt::Format)]
+pub enum Error<E> {
+    /// I2C bus error
+    Bus(E),
+    /// The chip answered with an unknown chip ID
+    UnsupportedChip(u8),
+    /// The GT911 answered with an unknown product ID string
+    UnsupportedProduct([u8; 4]),
+    /// A register read back a different value than was written to it
+    Verify { reg: u16, wrote: u8, read: u8 },
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::Bus(e)
+    }
+}
+
+/// Common interface of the touch controller drivers
+pub trait TouchController {
+    /// Driver specific operating parameters
+    type Config;
+    type Error;
+
+    /// Probe the chip and apply `config`
+    fn init(&mut self, config: Self::Config) -> Result<(), Self::Error>;
+
+    /// Hardware reset through the reset pin
+    fn reset<P: OutputPin, D: DelayNs>(&mut self, rst: &mut P, delay: &mut D) -> Result<(), P::Error>;
+
+    /// Read the first touch point, `None` if the panel is not touched
+    fn read_report(&mut self) -> Result<Option<PointEvent>, Self::Error>;
+
+    /// Read the gesture detected by the controller, if it supports gestures
+    fn read_gesture(&mut self) -> Result<Option<Gesture>, Self::Error> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,173 @@
+//! Raw touch to screen coordinate mapping
+
+use slint::platform::software_renderer::RenderingRotation;
+use slint::LogicalPosition;
+
+/// Affine transform from raw touch controller coordinates to screen coordinates
+///
+/// The last row of the matrix is always `[0, 0, 1]`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TouchTransform {
+    pub m: [[f32; 3]; 3],
+}
+
+/// A screen target and the raw coordinates reported when touching it
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CalibrationPoint {
+    pub screen: (f32, f32),
+    pub raw: (f32, f32),
+}
+
+impl TouchTransform {
+    pub const IDENTITY: Self = TouchTransform {
+        m: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    };
+
+    /// Apply `other` after `self`
+    pub fn then(self, other: Self) -> Self {
+        let (a, b) = (other.m, self.m);
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j];
+            }
+        }
+        TouchTransform { m }
+    }
+
+    /// Swap the X and Y axes
+    pub fn swap_xy(self) -> Self {
+        self.then(TouchTransform {
+            m: [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+        })
+    }
+
+    /// Mirror along X, `width` being the size of the axis
+    pub fn mirror_x(self, width: f32) -> Self {
+        self.then(TouchTransform {
+            m: [[-1.0, 0.0, width], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        })
+    }
+
+    /// Mirror along Y, `height` being the size of the axis
+    pub fn mirror_y(self, height: f32) -> Self {
+        self.then(TouchTransform {
+            m: [[1.0, 0.0, 0.0], [0.0, -1.0, height], [0.0, 0.0, 1.0]],
+        })
+    }
+
+    pub fn scale(self, sx: f32, sy: f32) -> Self {
+        self.then(TouchTransform {
+            m: [[sx, 0.0, 0.0], [0.0, sy, 0.0], [0.0, 0.0, 1.0]],
+        })
+    }
+
+    pub fn offset(self, dx: f32, dy: f32) -> Self {
+        self.then(TouchTransform {
+            m: [[1.0, 0.0, dx], [0.0, 1.0, dy], [0.0, 0.0, 1.0]],
+        })
+    }
+
+    /// Map panel coordinates to the UI rendered with `rotation`, see `EventLoop::set_rotation`
+    ///
+    /// `width` and `height` are the native size of the panel.
+    pub fn rotate(self, rotation: RenderingRotation, width: f32, height: f32) -> Self {
+        match rotation {
+            RenderingRotation::Rotate90 => self.swap_xy().mirror_y(width),
+            RenderingRotation::Rotate180 => self.mirror_x(width).mirror_y(height),
+            RenderingRotation::Rotate270 => self.swap_xy().mirror_x(height),
+            _ => self,
+        }
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let m = &self.m;
+        (m[0][0] * x + m[0][1] * y + m[0][2], m[1][0] * x + m[1][1] * y + m[1][2])
+    }
+
+    pub fn to_logical(&self, x: u16, y: u16) -> LogicalPosition {
+        let (x, y) = self.apply(x as f32, y as f32);
+        LogicalPosition { x, y }
+    }
+
+    /// Inverse transform, `None` if the matrix is degenerated
+    pub fn inverse(&self) -> Option<Self> {
+        let [[a, b, c], [d, e, f], _] = self.m;
+        let det = a * e - b * d;
+        if is_zero(det) {
+            return None;
+        }
+        Some(TouchTransform {
+            m: [
+                [e / det, -b / det, (b * f - c * e) / det],
+                [-d / det, a / det, (c * d - a * f) / det],
+                [0.0, 0.0, 1.0],
+            ],
+        })
+    }
+
+    /// Compute the transform from 3 or more calibration points
+    ///
+    /// 3 points give an exact solution, more points are fitted with least squares.
+    /// Returns `None` if there are too few points or they are collinear.
+    pub fn from_calibration(points: &[CalibrationPoint]) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+
+        // Normal equations: (AᵀA) [a b c]ᵀ = Aᵀ s, with rows of A being [x y 1]
+        let mut ata = [[0.0f32; 3]; 3];
+        let mut atx = [0.0f32; 3];
+        let mut aty = [0.0f32; 3];
+        for p in points {
+            let row = [p.raw.0, p.raw.1, 1.0];
+            for (i, ri) in row.iter().enumerate() {
+                for (j, rj) in row.iter().enumerate() {
+                    ata[i][j] += ri * rj;
+                }
+                atx[i] += ri * p.screen.0;
+                aty[i] += ri * p.screen.1;
+            }
+        }
+
+        let x = solve3(&ata, &atx)?;
+        let y = solve3(&ata, &aty)?;
+        Some(TouchTransform {
+            m: [x, y, [0.0, 0.0, 1.0]],
+        })
+    }
+}
+
+fn det3(m: &[[f32; 3]; 3]) -> f32 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// Solve `m * x = v` with Cramer's rule
+fn solve3(m: &[[f32; 3]; 3], v: &[f32; 3]) -> Option<[f32; 3]> {
+    let det = det3(m);
+    if is_zero(det) {
+        return None;
+    }
+    let mut x = [0.0; 3];
+    for (col, x) in x.iter_mut().enumerate() {
+        let mut mc = *m;
+        for (row, v) in mc.iter_mut().zip(v) {
+            row[col] = *v;
+        }
+        *x = det3(&mc) / det;
+    }
+    Some(x)
+}
+
+/// `f32::abs` is not available in `core`
+fn is_zero(v: f32) -> bool {
+    -f32::EPSILON < v && v < f32::EPSILON
+}
+
+impl Default for TouchTransform {
+    /// The FT6236 glass of the RM67162 board, in landscape orientation
+    fn default() -> Self {
+        TouchTransform::IDENTITY.swap_xy().mirror_y(240.0)
+    }
+}